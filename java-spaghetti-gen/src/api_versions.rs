@@ -0,0 +1,284 @@
+//! Reader for the Android SDK's `api-versions.xml` (`platforms/android-NN/data/api-versions.xml`), which records
+//! the API levels in which each class, method and field was added, deprecated and removed.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+use anyhow::{Context as _, anyhow};
+
+/// API levels of a class or member. Members without a `since` level have the one of their class.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ApiLevels {
+    pub since: Option<u32>,
+    pub deprecated: Option<u32>,
+    pub removed: Option<u32>,
+}
+
+impl ApiLevels {
+    fn inherit(self, class: ApiLevels) -> Self {
+        Self {
+            since: self.since.or(class.since),
+            ..self
+        }
+    }
+}
+
+impl fmt::Display for ApiLevels {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut sep = "";
+        for (what, level) in [
+            ("Added", self.since),
+            ("Deprecated", self.deprecated),
+            ("Removed", self.removed),
+        ] {
+            if let Some(level) = level {
+                write!(f, "{sep}{what} in API level {level}.")?;
+                sep = " ";
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+struct ClassApi {
+    levels: ApiLevels,
+    /// Keyed by name and descriptor, e.g. `<init>(Ljava/lang/String;)V`.
+    methods: HashMap<String, ApiLevels>,
+    fields: HashMap<String, ApiLevels>,
+}
+
+#[derive(Debug, Default)]
+pub struct ApiVersions {
+    classes: HashMap<String, ClassApi>,
+}
+
+impl ApiVersions {
+    pub fn read(path: &Path) -> anyhow::Result<Self> {
+        let xml = std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        Self::parse(&xml).with_context(|| format!("parsing {}", path.display()))
+    }
+
+    fn parse(xml: &str) -> anyhow::Result<Self> {
+        let mut res = Self::default();
+        let mut class: Option<(String, ClassApi)> = None;
+
+        for tag in Tags(xml) {
+            let tag = tag?;
+            match tag.name {
+                "class" => {
+                    let name = tag.attr("name")?;
+                    let api = ClassApi {
+                        levels: tag.levels()?,
+                        ..Default::default()
+                    };
+                    if tag.self_closing {
+                        res.classes.insert(name, api);
+                    } else {
+                        class = Some((name, api));
+                    }
+                }
+                "/class" => {
+                    if let Some((name, api)) = class.take() {
+                        res.classes.insert(name, api);
+                    }
+                }
+                "method" | "field" => {
+                    let (_, api) = class
+                        .as_mut()
+                        .ok_or_else(|| anyhow!("<{}> outside of <class>", tag.name))?;
+                    let members = match tag.name {
+                        "method" => &mut api.methods,
+                        _ => &mut api.fields,
+                    };
+                    members.insert(tag.attr("name")?, tag.levels()?);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(res)
+    }
+
+    pub fn class(&self, class: &str) -> Option<ApiLevels> {
+        self.classes.get(class).map(|c| c.levels)
+    }
+
+    pub fn method(&self, class: &str, name: &str, descriptor: &str) -> Option<ApiLevels> {
+        let c = self.classes.get(class)?;
+        let levels = c.methods.get(&format!("{name}{descriptor}"))?;
+        Some(levels.inherit(c.levels))
+    }
+
+    pub fn field(&self, class: &str, name: &str) -> Option<ApiLevels> {
+        let c = self.classes.get(class)?;
+        Some(c.fields.get(name)?.inherit(c.levels))
+    }
+}
+
+/// A start or end tag, with its raw attributes.
+struct Tag<'a> {
+    /// Tag name, prefixed with `/` for end tags.
+    name: &'a str,
+    attrs: &'a str,
+    self_closing: bool,
+}
+
+impl Tag<'_> {
+    fn get(&self, attr: &str) -> Option<String> {
+        let mut rest = self.attrs;
+        while let Some((key, after)) = rest.split_once('=') {
+            let after = after.trim_start();
+            let quote = after.chars().next()?;
+            let (value, after) = after[1..].split_once(quote)?;
+            if key.trim() == attr {
+                return Some(unescape(value));
+            }
+            rest = after;
+        }
+        None
+    }
+
+    fn attr(&self, attr: &str) -> anyhow::Result<String> {
+        self.get(attr)
+            .ok_or_else(|| anyhow!("<{}> without a {attr:?} attribute", self.name))
+    }
+
+    fn level(&self, attr: &str) -> anyhow::Result<Option<u32>> {
+        self.get(attr)
+            .map(|level| level.parse().with_context(|| format!("invalid {attr} level {level:?}")))
+            .transpose()
+    }
+
+    fn levels(&self) -> anyhow::Result<ApiLevels> {
+        Ok(ApiLevels {
+            since: self.level("since")?,
+            deprecated: self.level("deprecated")?,
+            removed: self.level("removed")?,
+        })
+    }
+}
+
+/// Iterates over the tags of an XML document, skipping text, comments and declarations.
+struct Tags<'a>(&'a str);
+
+impl<'a> Iterator for Tags<'a> {
+    type Item = anyhow::Result<Tag<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let start = self.0.find('<')?;
+            let rest = &self.0[start + 1..];
+            if let Some(comment) = rest.strip_prefix("!--") {
+                let Some(end) = comment.find("-->") else {
+                    return Some(Err(anyhow!("unterminated comment")));
+                };
+                self.0 = &comment[end + 3..];
+                continue;
+            }
+            // Attribute values may contain unescaped `>`, as in `name="&lt;init>()V"`.
+            let mut quote = None;
+            let end = rest.find(|c| {
+                match (quote, c) {
+                    (None, '"' | '\'') => quote = Some(c),
+                    (Some(q), c) if q == c => quote = None,
+                    (None, '>') => return true,
+                    _ => {}
+                }
+                false
+            });
+            let Some(end) = end else {
+                return Some(Err(anyhow!("unterminated tag")));
+            };
+            self.0 = &rest[end + 1..];
+            let tag = &rest[..end];
+            if tag.starts_with(['?', '!']) {
+                continue;
+            }
+            let (tag, self_closing) = match tag.strip_suffix('/') {
+                Some(tag) => (tag, true),
+                None => (tag, false),
+            };
+            let (name, attrs) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+            return Some(Ok(Tag {
+                name,
+                attrs,
+                self_closing,
+            }));
+        }
+    }
+}
+
+fn unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XML: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<api version="3" min="1">
+    <!-- a comment, with <tags> -->
+    <class name="android/app/Activity" since="1">
+        <extends name="android/view/ContextThemeWrapper"/>
+        <method name="&lt;init>()V"/>
+        <method name="getActionBar()Landroid/app/ActionBar;" since="11"/>
+        <method name="onBackPressed()V" since="5" deprecated="33"/>
+        <field name="FOCUSED_STATE_SET" since="1" removed="30"/>
+    </class>
+    <class name="android/app/Fragment" since="11" deprecated="28"/>
+</api>
+"#;
+
+    #[test]
+    fn parse() {
+        let api = ApiVersions::parse(XML).unwrap();
+        let levels = |since, deprecated, removed| ApiLevels {
+            since,
+            deprecated,
+            removed,
+        };
+
+        assert_eq!(api.class("android/app/Activity"), Some(levels(Some(1), None, None)));
+        assert_eq!(
+            api.class("android/app/Fragment"),
+            Some(levels(Some(11), Some(28), None))
+        );
+        assert_eq!(api.class("android/app/Dialog"), None);
+
+        assert_eq!(
+            api.method("android/app/Activity", "<init>", "()V"),
+            Some(levels(Some(1), None, None))
+        );
+        assert_eq!(
+            api.method("android/app/Activity", "getActionBar", "()Landroid/app/ActionBar;"),
+            Some(levels(Some(11), None, None))
+        );
+        assert_eq!(
+            api.method("android/app/Activity", "onBackPressed", "()V"),
+            Some(levels(Some(5), Some(33), None))
+        );
+        assert_eq!(api.method("android/app/Activity", "finish", "()V"), None);
+        assert_eq!(
+            api.field("android/app/Activity", "FOCUSED_STATE_SET"),
+            Some(levels(Some(1), None, Some(30)))
+        );
+    }
+
+    #[test]
+    fn display() {
+        let levels = ApiLevels {
+            since: Some(5),
+            deprecated: Some(33),
+            removed: None,
+        };
+        assert_eq!(levels.to_string(), "Added in API level 5. Deprecated in API level 33.");
+        assert_eq!(ApiLevels::default().to_string(), "");
+    }
+}
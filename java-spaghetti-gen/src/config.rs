@@ -9,6 +9,10 @@ fn default_proxy_package() -> String {
     "java_spaghetti/proxy".to_string()
 }
 
+fn default_proxy_panic_exception() -> String {
+    "java/lang/RuntimeException".to_string()
+}
+
 fn default_slash() -> String {
     String::from("/")
 }
@@ -153,6 +157,28 @@ pub struct Rule {
     #[serde(default)]
     pub proxy: Option<bool>,
 
+    /// Also generate `try_` variants of methods and fields, returning a `LookupError` instead of panicking
+    /// if the class or member is not available at runtime. Variants whose name is already used by a method or field of
+    /// the class are left out.
+    #[serde(default)]
+    pub try_variants: Option<bool>,
+
+    /// Also generate `super_` variants of overridable methods, calling this class's implementation with JNI
+    /// `CallNonvirtual<Type>MethodA` even if it is overridden, e.g. from a proxy subclass. Overridable methods are
+    /// instance methods with a body, neither private nor final, of classes that aren't final. Variants whose name is
+    /// already used by a method or field of the class are left out.
+    #[serde(default)]
+    pub super_variants: Option<bool>,
+
+    /// Return the exceptions declared by the `throws` clause of a method as a generated `{Class}{Method}Error` enum,
+    /// instead of a `Local<Throwable>`. Only exception classes that are included become variants.
+    #[serde(default)]
+    pub typed_throws: Option<bool>,
+
+    /// Leave out classes, methods and fields added after this Android API level. Requires `api_versions`.
+    #[serde(default)]
+    pub min_api_level: Option<u32>,
+
     #[serde(default)]
     pub doc_pattern: Option<DocPattern>,
 }
@@ -165,6 +191,10 @@ pub struct ClassConfig<'a> {
     pub include_private_methods: bool,
     pub include_private_fields: bool,
     pub proxy: bool,
+    pub try_variants: bool,
+    pub super_variants: bool,
+    pub typed_throws: bool,
+    pub min_api_level: Option<u32>,
     pub doc_pattern: Option<&'a DocPattern>,
 }
 
@@ -177,6 +207,21 @@ pub struct Config {
     pub proxy_package: String,
     #[serde(default)]
     pub proxy_output: Option<PathBuf>,
+    /// Exception class thrown to Java when a proxy implementation panics. It must have a `(String)` constructor.
+    #[serde(default = "default_proxy_panic_exception")]
+    pub proxy_panic_exception: String,
+    /// Bind the proxy native methods with JNI `RegisterNatives` instead of exporting `Java_...` symbols.
+    #[serde(default)]
+    pub proxy_register_natives: bool,
+    /// Embed the proxy classes as bytecode in the generated Rust, to be defined with JNI `DefineClass` on first use,
+    /// instead of compiling the Java proxy sources. Implies `proxy_register_natives`. Not supported on Android.
+    #[serde(default)]
+    pub proxy_embed_bytecode: bool,
+
+    /// The Android SDK's `platforms/android-NN/data/api-versions.xml`, to document the API levels in which classes and
+    /// members were added, deprecated and removed, and to filter them with `min_api_level`.
+    #[serde(default)]
+    pub api_versions: Option<PathBuf>,
 
     #[serde(default)]
     pub logging_verbose: bool,
@@ -212,6 +257,9 @@ impl Config {
         if let Some(proxy_output) = &mut config.proxy_output {
             *proxy_output = resolve_file(proxy_output, dir);
         }
+        if let Some(api_versions) = &mut config.api_versions {
+            *api_versions = resolve_file(api_versions, dir);
+        }
         for f in &mut config.input {
             *f = resolve_file(f, dir);
         }
@@ -263,6 +311,10 @@ impl Config {
             include_private_methods: false,
             include_private_fields: false,
             proxy: false,
+            try_variants: false,
+            super_variants: false,
+            typed_throws: false,
+            min_api_level: None,
             doc_pattern: None,
         };
 
@@ -283,6 +335,18 @@ impl Config {
                 if let Some(proxy) = r.proxy {
                     res.proxy = proxy;
                 }
+                if let Some(try_variants) = r.try_variants {
+                    res.try_variants = try_variants;
+                }
+                if let Some(super_variants) = r.super_variants {
+                    res.super_variants = super_variants;
+                }
+                if let Some(typed_throws) = r.typed_throws {
+                    res.typed_throws = typed_throws;
+                }
+                if let Some(min_api_level) = r.min_api_level {
+                    res.min_api_level = Some(min_api_level);
+                }
                 if let Some(doc_pattern) = &r.doc_pattern {
                     res.doc_pattern = Some(doc_pattern);
                 }
@@ -1,40 +1,52 @@
 use std::fmt::Write;
 
 use cafebabe::descriptors::{FieldDescriptor, FieldType, ReturnDescriptor};
-use proc_macro2::TokenStream;
+use proc_macro2::{Literal, TokenStream};
 use quote::{format_ident, quote};
 
 use super::classes::Class;
 use super::cstring;
-use super::fields::RustTypeFlavor;
+use super::fields::{RustTypeFlavor, emit_fragment_type};
 use super::methods::Method;
 use crate::emit::Context;
 use crate::emit::fields::emit_type;
-use crate::parser_util::Id;
 
 impl Class {
     #[allow(clippy::vec_init_then_push)]
-    pub(crate) fn write_proxy(&self, context: &Context, methods: &[Method]) -> anyhow::Result<TokenStream> {
+    /// Emits the proxy trait and `new_proxy()`, and `from_fn()` if `functional` is the single abstract method of a
+    /// functional interface.
+    pub(crate) fn write_proxy(
+        &self,
+        context: &Context,
+        methods: &[Method],
+        functional: Option<&Method>,
+    ) -> anyhow::Result<TokenStream> {
         let mut emit_reject_reasons = Vec::new();
 
         let mut out = TokenStream::new();
         let mut contents = TokenStream::new();
 
-        let rust_name = format_ident!("{}", &self.rust.struct_name);
+        let struct_name = format_ident!("{}", &self.rust.struct_name);
 
-        let object = context
-            .java_to_rust_path(Id("java/lang/Object"), &self.rust.mod_)
-            .unwrap();
         let throwable = context.throwable_rust_path(&self.rust.mod_);
         let rust_proxy_name = format_ident!("{}Proxy", &self.rust.struct_name);
 
         let mut trait_methods = TokenStream::new();
+        let mut native_methods = TokenStream::new();
+        let mut from_fn_methods = TokenStream::new();
+        let mut from_fn_bound = None;
+        let mut has_defaults = false;
 
-        let java_proxy_path = format!(
-            "{}/{}",
-            context.config.proxy_package,
-            self.java.path().as_str().replace("$", "_")
-        );
+        // Natives of classes defined at runtime can't be found by their exported symbol.
+        let register_natives = context.config.proxy_register_natives || context.config.proxy_embed_bytecode;
+        let no_mangle = match register_natives {
+            true => quote!(),
+            false => quote!(#[unsafe(no_mangle)]),
+        };
+
+        let panic_exception = cstring(&context.config.proxy_panic_exception);
+
+        let java_proxy_path = self.java_proxy_path(context);
 
         for method in methods {
             let Some(rust_name) = method.rust_name() else { continue };
@@ -61,6 +73,17 @@ impl Class {
             let native_name = format_ident!("{native_name}");
             let rust_name = format_ident!("{rust_name}");
 
+            // The native method takes the `long ptr` before the proxied method's arguments.
+            let native_java_name = cstring(&format!("native_{}", method.java.name()));
+            let native_descriptor = cstring(&format!("(J{}", &method.java.descriptor.to_string()[1..]));
+            native_methods.extend(quote!(
+                ::java_spaghetti::sys::JNINativeMethod {
+                    name: #native_java_name.as_ptr() as *mut _,
+                    signature: #native_descriptor.as_ptr() as *mut _,
+                    fnPtr: #native_name as *mut _,
+                },
+            ));
+
             let ret = match &method.java.descriptor.return_type {
                 ReturnDescriptor::Void => quote!(()),
                 ReturnDescriptor::Return(desc) => emit_type(
@@ -73,8 +96,10 @@ impl Class {
             };
 
             let mut trait_args = TokenStream::new();
+            let mut trait_arg_types = Vec::new();
             let mut native_args = TokenStream::new();
             let mut native_convert_args = TokenStream::new();
+            let mut native_jvalues = TokenStream::new();
 
             for (arg_idx, arg) in method.java.descriptor.parameters.iter().enumerate() {
                 let arg_name = format_ident!("arg{}", arg_idx);
@@ -87,6 +112,7 @@ impl Class {
                     &mut emit_reject_reasons,
                 )?;
                 trait_args.extend(quote!(#arg_name: #trait_arg_type,));
+                trait_arg_types.push(trait_arg_type);
 
                 let native_arg_type = emit_type(
                     arg,
@@ -98,8 +124,10 @@ impl Class {
                 native_args.extend(quote!(#arg_name: #native_arg_type,));
                 if matches!(arg.field_type, FieldType::Object(_)) || arg.dimensions > 0 {
                     native_convert_args.extend(quote!(#arg_name.into_ref(__jni_env),));
+                    native_jvalues.extend(quote!(::java_spaghetti::sys::jvalue { l: #arg_name.as_raw() },));
                 } else {
                     native_convert_args.extend(quote!(#arg_name,));
+                    native_jvalues.extend(quote!(::java_spaghetti::AsJValue::as_jvalue(&#arg_name),));
                 }
             }
 
@@ -111,18 +139,135 @@ impl Class {
                 ) -> #ret;
             ));
 
+            // Default methods of interfaces, which `from_fn()` proxies leave to their Java implementation. So are the
+            // methods of `java.lang.Object` that interfaces redeclare, e.g. `Comparator.equals()`.
+            let is_object_method = self.java.is_interface() && method.is_object_method();
+            let is_default = self.java.is_interface() && (!method.java.is_abstract() || is_object_method);
+            has_defaults |= is_default;
+
+            if functional.is_some_and(|functional| std::ptr::eq(functional, method)) {
+                let arg_names: Vec<_> = (0..trait_arg_types.len()).map(|i| format_ident!("arg{}", i)).collect();
+                from_fn_methods.extend(quote!(
+                    fn #rust_name<'env>(
+                        &self,
+                        env: ::java_spaghetti::Env<'env>,
+                        #(#arg_names: #trait_arg_types,)*
+                    ) -> #ret {
+                        (self.0)(env, #(#arg_names),*)
+                    }
+                ));
+                from_fn_bound = Some(quote!(
+                    for<'env> Fn(::java_spaghetti::Env<'env>, #(#trait_arg_types),*) -> #ret
+                        + ::std::marker::Send + ::std::marker::Sync + 'static
+                ));
+            } else if is_default {
+                let message = format!(
+                    "{}() runs its Java implementation for {}::from_fn()",
+                    method.java.name(),
+                    self.rust.struct_name
+                );
+                from_fn_methods.extend(quote!(
+                    fn #rust_name<'env>(
+                        &self,
+                        _: ::java_spaghetti::Env<'env>,
+                        #(_: #trait_arg_types,)*
+                    ) -> #ret {
+                        unreachable!(#message)
+                    }
+                ));
+            } else {
+                let message = format!(
+                    "{}() is not implemented by {}::from_fn()",
+                    method.java.name(),
+                    self.rust.struct_name
+                );
+                from_fn_methods.extend(quote!(
+                    fn #rust_name<'env>(
+                        &self,
+                        _: ::java_spaghetti::Env<'env>,
+                        #(_: #trait_arg_types,)*
+                    ) -> #ret {
+                        panic!(#message)
+                    }
+                ));
+            }
+
+            if !is_default {
+                out.extend(quote!(
+                    #no_mangle
+                    extern "system" fn #native_name<'env>(
+                        __jni_env: ::java_spaghetti::Env<'env>,
+                        _class: *mut (), // self class, ignore
+                        ptr: i64,
+                        #native_args
+                    ) -> #ret {
+                        let ptr: *const std::sync::Arc<dyn #rust_proxy_name> = ::std::ptr::with_exposed_provenance(ptr as usize);
+                        __jni_env.catch_unwind(#panic_exception, || unsafe {
+                            (*ptr).#rust_name(__jni_env, #native_convert_args )
+                        })
+                    }
+                ));
+                continue;
+            }
+
+            // Unless the proxy implements the default method, it is called non-virtually on the proxy object, like
+            // `Interface.super.method()` would in Java. The methods of `java.lang.Object` call its implementation.
+            let class = match is_object_method {
+                false => quote!(#struct_name::__class_global_ref(__jni_env)),
+                true => quote!({
+                    static __CLASS: ::std::sync::OnceLock<::java_spaghetti::Global<::java_spaghetti::JObject>> =
+                        ::std::sync::OnceLock::new();
+                    __CLASS
+                        .get_or_init(|| __jni_env.require_class_global(c"java/lang/Object"))
+                        .as_raw()
+                }),
+            };
+            let java_name = cstring(method.java.name());
+            let descriptor = cstring(&method.java.descriptor().to_string());
+            let (call, convert_ret) = match &method.java.descriptor.return_type {
+                ReturnDescriptor::Void => (format_ident!("call_nonvirtual_void_method_a"), quote!(value)),
+                ReturnDescriptor::Return(desc) => {
+                    let call = format_ident!("call_nonvirtual_{}_method_a", emit_fragment_type(desc));
+                    match emit_fragment_type(desc) {
+                        "object" => (
+                            call,
+                            quote!(
+                                value.map_or_else(::java_spaghetti::Return::null, ::java_spaghetti::Local::into_return)
+                            ),
+                        ),
+                        _ => (call, quote!(value)),
+                    }
+                }
+            };
             out.extend(quote!(
-                #[unsafe(no_mangle)]
+                #no_mangle
                 extern "system" fn #native_name<'env>(
                     __jni_env: ::java_spaghetti::Env<'env>,
-                    _class: *mut (), // self class, ignore
+                    __jni_this: ::java_spaghetti::sys::jobject,
                     ptr: i64,
                     #native_args
                 ) -> #ret {
                     let ptr: *const std::sync::Arc<dyn #rust_proxy_name> = ::std::ptr::with_exposed_provenance(ptr as usize);
-                    unsafe {
-                        (*ptr).#rust_name(__jni_env, #native_convert_args )
-                    }
+                    __jni_env.catch_unwind(#panic_exception, || unsafe {
+                        if !(*ptr).java_defaults() {
+                            return (*ptr).#rust_name(__jni_env, #native_convert_args );
+                        }
+                        static __METHOD: ::std::sync::OnceLock<::java_spaghetti::JMethodID> = ::std::sync::OnceLock::new();
+                        let __jni_args = [#native_jvalues];
+                        let __jni_class = #class;
+                        let __jni_method = __METHOD.get_or_init(||
+                            ::java_spaghetti::JMethodID::from_raw(__jni_env.require_method(__jni_class, #java_name, #descriptor))
+                        ).as_raw();
+                        let __jni_result: ::std::result::Result<_, ::java_spaghetti::Local<'env, #throwable>> =
+                            __jni_env.#call(__jni_this, __jni_class, __jni_method, __jni_args.as_ptr());
+                        match __jni_result {
+                            Ok(value) => #convert_ret,
+                            Err(exception) => {
+                                __jni_env.throw(&*exception);
+                                ::std::default::Default::default()
+                            }
+                        }
+                    })
                 }
             ));
         }
@@ -134,38 +279,107 @@ impl Class {
         });
         let native_name = mangle_native_method(&java_proxy_path, "native_finalize", &native_params);
         let native_name = format_ident!("{native_name}");
+        native_methods.extend(quote!(
+            ::java_spaghetti::sys::JNINativeMethod {
+                name: c"native_finalize".as_ptr() as *mut _,
+                signature: c"(J)V".as_ptr() as *mut _,
+                fnPtr: #native_name as *mut _,
+            },
+        ));
+
+        if has_defaults {
+            trait_methods.extend(quote!(
+                /// Whether calls of the `default` methods of the interface, and of the methods of `java.lang.Object`
+                /// it redeclares, run their Java implementation instead of the methods of this trait. `false` unless
+                /// overridden.
+                fn java_defaults(&self) -> bool {
+                    false
+                }
+            ));
+            from_fn_methods.extend(quote!(
+                fn java_defaults(&self) -> bool {
+                    true
+                }
+            ));
+        }
 
         out.extend(quote!(
             pub trait #rust_proxy_name: ::std::marker::Send + ::std::marker::Sync + 'static {
                 #trait_methods
             }
 
-            #[unsafe(no_mangle)]
+            #no_mangle
             extern "system" fn #native_name(
                 __jni_env: ::java_spaghetti::Env<'_>,
                 _class: *mut (), // self class, ignore
                 ptr: i64,
             ) {
                 let ptr: *mut std::sync::Arc<dyn #rust_proxy_name> = ::std::ptr::with_exposed_provenance_mut(ptr as usize);
-                let _ = unsafe { Box::from_raw(ptr) };
+                __jni_env.catch_unwind(#panic_exception, || {
+                    let _ = unsafe { Box::from_raw(ptr) };
+                })
             }
         ));
 
-        let java_proxy_path = cstring(&java_proxy_path);
+        let java_proxy_class = cstring(&java_proxy_path);
+
+        let load_class = if context.config.proxy_embed_bytecode {
+            let bytecode = Literal::byte_string(&self.write_java_proxy_bytecode(context)?);
+            let message = format!("couldn't define proxy class {java_proxy_path}");
+            quote!(
+                static __BYTECODE: &[u8] = #bytecode;
+                let __jni_class = __jni_env.define_class::<#throwable>(#java_proxy_class, __BYTECODE).expect(#message);
+            )
+        } else {
+            quote!(let __jni_class = __jni_env.require_class(#java_proxy_class);)
+        };
+
+        contents.extend(quote!(
+            fn __proxy_class_global_ref(__jni_env: ::java_spaghetti::Env) -> ::java_spaghetti::sys::jobject {
+                static __CLASS: ::std::sync::OnceLock<::java_spaghetti::Global<::java_spaghetti::JObject>> =
+                    ::std::sync::OnceLock::new();
+                __CLASS
+                    .get_or_init(|| unsafe {
+                        #load_class
+                        __jni_env.class_global(__jni_class)
+                    })
+                    .as_raw()
+            }
+        ));
+
+        let register_call = if register_natives {
+            let docs = format!(
+                "Binds the native methods of the proxy class `{}` with JNI `RegisterNatives`.\n\n\
+                 This is done by [{}::new_proxy] on its first call, but it may be called earlier \
+                 (e.g. in `JNI_OnLoad`) if proxy objects can reach Java some other way.",
+                java_proxy_path, self.rust.struct_name
+            );
+            contents.extend(quote!(
+                #[doc = #docs]
+                pub fn register_proxy_natives<'env>(
+                    env: ::java_spaghetti::Env<'env>,
+                ) -> Result<(), ::java_spaghetti::Local<'env, #throwable>> {
+                    unsafe { env.register_natives(Self::__proxy_class_global_ref(env), &[#native_methods]) }
+                }
+            ));
+            quote! {
+                static __NATIVES: ::std::sync::OnceLock<()> = ::std::sync::OnceLock::new();
+                if __NATIVES.get().is_none() {
+                    Self::register_proxy_natives(env)?;
+                    let _ = __NATIVES.set(());
+                }
+            }
+        } else {
+            quote!()
+        };
 
         contents.extend(quote!(
             pub fn new_proxy<'env>(
                 env: ::java_spaghetti::Env<'env>,
                 proxy: ::std::sync::Arc<dyn #rust_proxy_name>,
             ) -> Result<::java_spaghetti::Local<'env, Self>, ::java_spaghetti::Local<'env, #throwable>> {
-                static __CLASS: ::std::sync::OnceLock<::java_spaghetti::Global<#object>> =
-                    ::std::sync::OnceLock::new();
-                let __jni_class = __CLASS
-                    .get_or_init(|| unsafe {
-                        ::java_spaghetti::Local::from_raw(env, env.require_class(#java_proxy_path),)
-                        .as_global()
-                    })
-                    .as_raw();
+                let __jni_class = Self::__proxy_class_global_ref(env);
+                #register_call
 
                 let b = ::std::boxed::Box::new(proxy);
                 let ptr = ::std::boxed::Box::into_raw(b);
@@ -189,7 +403,32 @@ impl Class {
             }
         ));
 
-        out.extend(quote!(impl #rust_name { #contents }));
+        if let Some(bound) = from_fn_bound {
+            let docs = format!(
+                "Creates a proxy calling `f` for [{0}Proxy::{1}]. The `default` methods of the interface run \
+                 their Java implementation, and its other methods panic, which throws a `{2}` to Java.",
+                self.rust.struct_name,
+                functional.unwrap().rust_name().unwrap(),
+                context.config.proxy_panic_exception.replace('/', "."),
+            );
+            contents.extend(quote!(
+                #[doc = #docs]
+                pub fn from_fn<'a>(
+                    env: ::java_spaghetti::Env<'a>,
+                    f: impl #bound,
+                ) -> Result<::java_spaghetti::Local<'a, Self>, ::java_spaghetti::Local<'a, #throwable>> {
+                    struct FromFn<F>(F);
+
+                    impl<F: #bound> #rust_proxy_name for FromFn<F> {
+                        #from_fn_methods
+                    }
+
+                    Self::new_proxy(env, ::std::sync::Arc::new(FromFn(f)))
+                }
+            ));
+        }
+
+        out.extend(quote!(impl #struct_name { #contents }));
 
         if !emit_reject_reasons.is_empty() {
             // TODO log
@@ -228,3 +467,46 @@ fn mangle_native(s: &str) -> String {
     }
     res
 }
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_classes::*;
+
+    #[test]
+    fn test_from_fn_leaves_default_methods_to_java() {
+        let classes = [
+            TestClass::class("java/lang/Object"),
+            TestClass::class("java/lang/Throwable"),
+            TestClass::interface("com/example/Sink")
+                .method(ACC_PUBLIC | ACC_ABSTRACT, "accept", "(Ljava/lang/Object;)V")
+                .method(ACC_PUBLIC, "andThen", "(Lcom/example/Sink;)Lcom/example/Sink;")
+                .method(ACC_PUBLIC | ACC_ABSTRACT, "equals", "(Ljava/lang/Object;)Z"),
+        ];
+        let out = generate(
+            "rules:\n  - match: \"**\"\n    include: true\n  - match: \"com/example/Sink\"\n    proxy: true\n",
+            &classes,
+        );
+
+        // The trait keeps `andThen()`, and `from_fn()` proxies call the Java implementation for it.
+        assert!(out.contains(&compact("fn java_defaults(&self) -> bool { false }")));
+        assert!(out.contains(&compact("fn java_defaults(&self) -> bool { true }")));
+        assert_eq!(out.matches(&compact("if !(*ptr).java_defaults()")).count(), 2);
+        assert!(out.contains(&compact(
+            "__jni_env.call_nonvirtual_object_method_a(__jni_this, __jni_class, __jni_method, __jni_args.as_ptr())"
+        )));
+        assert!(out.contains(&compact(
+            r#"unreachable!("andThen() runs its Java implementation for Sink::from_fn()")"#
+        )));
+        assert!(!out.contains(&compact(r#"panic!("andThen()"#)));
+
+        // The abstract `equals()` is still `Object.equals()`, rather than a second abstract method.
+        assert!(out.contains(&compact(
+            "__jni_env.call_nonvirtual_boolean_method_a(__jni_this, __jni_class, __jni_method, __jni_args.as_ptr())"
+        )));
+        assert!(out.contains(&compact(r#"require_class_global(c"java/lang/Object")"#)));
+        assert!(out.contains(&compact(
+            r#"unreachable!("equals() runs its Java implementation for Sink::from_fn()")"#
+        )));
+        assert!(!out.contains(&compact(r#"panic!("equals()"#)));
+    }
+}
@@ -1,13 +1,14 @@
 use std::collections::HashSet;
 use std::fmt::Write;
 
+use cafebabe::descriptors::ReturnDescriptor;
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
 
-use super::cstring;
-use super::fields::Field;
+use super::fields::{Field, RustTypeFlavor, emit_type};
 use super::known_docs_url::KnownDocsUrl;
 use super::methods::Method;
+use super::{api_levels_docs, cstring, is_available, preamble};
 use crate::emit::Context;
 use crate::identifiers::{FieldMangling, rust_ident};
 use crate::parser_util::{Id, IdPart, JavaClass};
@@ -101,6 +102,13 @@ impl Class {
             None => format!("{keyword} {}", self.java.path().as_str()),
         };
 
+        let api_docs = api_levels_docs(
+            context
+                .api_versions
+                .as_ref()
+                .and_then(|a| a.class(self.java.path().as_str())),
+        );
+
         let rust_name = format_ident!("{}", &self.rust.struct_name);
 
         let referencetype_impl = match self.java.is_static() {
@@ -108,16 +116,30 @@ impl Class {
             false => quote!(unsafe impl ::java_spaghetti::ReferenceType for #rust_name {}),
         };
 
+        let throwabletype_impl =
+            match !self.java.is_static() && context.is_subclass_of(self.java.path(), Id("java/lang/Throwable")) {
+                true => quote!(impl ::java_spaghetti::ThrowableType for #rust_name {}),
+                false => quote!(),
+            };
+
+        let bytebuffertype_impl = match self.java.path().as_str() {
+            "java/nio/ByteBuffer" => quote!(unsafe impl ::java_spaghetti::ByteBufferType for #rust_name {}),
+            _ => quote!(),
+        };
+
         let mut out = TokenStream::new();
 
         let java_path = cstring(self.java.path().as_str());
 
         out.extend(quote!(
             #[doc = #docs]
+            #api_docs
             #attributes
             #visibility enum #rust_name {}
 
             #referencetype_impl
+            #throwabletype_impl
+            #bytebuffertype_impl
 
             unsafe impl ::java_spaghetti::JniType for #rust_name {
                 fn static_with_jni_type<R>(callback: impl FnOnce(&::std::ffi::CStr) -> R) -> R {
@@ -126,23 +148,12 @@ impl Class {
             }
         ));
 
-        // recursively visit all superclasses and superinterfaces.
-        let mut queue = Vec::new();
-        let mut visited = HashSet::new();
-        queue.push(self.java.path());
-        visited.insert(self.java.path());
-        while let Some(path) = queue.pop() {
-            let class = context.all_classes.get(path.as_str()).unwrap();
-            for path2 in self.java.interfaces().map(|i| Id(i)).chain(class.java.super_path()) {
-                if context.all_classes.contains_key(path2.as_str()) && !visited.contains(&path2) {
-                    let rust_path = context.java_to_rust_path(path2, &self.rust.mod_).unwrap();
-                    out.extend(quote!(
-                        unsafe impl ::java_spaghetti::AssignableTo<#rust_path> for #rust_name {}
-                    ));
-                    queue.push(path2);
-                    visited.insert(path2);
-                }
-            }
+        let supertypes = self.supertypes(context);
+        for path in &supertypes {
+            let rust_path = context.java_to_rust_path(*path, &self.rust.mod_).unwrap();
+            out.extend(quote!(
+                unsafe impl ::java_spaghetti::AssignableTo<#rust_path> for #rust_name {}
+            ));
         }
 
         let mut contents = TokenStream::new();
@@ -153,34 +164,67 @@ impl Class {
 
         let class = cstring(self.java.path().as_str());
 
-        contents.extend(quote!(
-            fn __class_global_ref(__jni_env: ::java_spaghetti::Env) -> ::java_spaghetti::sys::jobject {
-                static __CLASS: ::std::sync::OnceLock<::java_spaghetti::Global<#object>> = ::std::sync::OnceLock::new();
-                __CLASS
-                    .get_or_init(|| unsafe {
-                        ::java_spaghetti::Local::from_raw(__jni_env, __jni_env.require_class(#class)).as_global()
-                    })
-                    .as_raw()
-            }
-        ));
+        if cc.try_variants {
+            // The class lookup may fail: only cache it once it succeeds.
+            contents.extend(quote!(
+                fn __class_global_ref(__jni_env: ::java_spaghetti::Env) -> ::java_spaghetti::sys::jobject {
+                    Self::__try_class_global_ref(__jni_env).unwrap_or_else(|e| panic!("{e}"))
+                }
+
+                fn __try_class_global_ref(
+                    __jni_env: ::java_spaghetti::Env,
+                ) -> ::std::result::Result<::java_spaghetti::sys::jobject, ::java_spaghetti::LookupError> {
+                    static __CLASS: ::std::sync::OnceLock<::java_spaghetti::Global<::java_spaghetti::JObject>> = ::std::sync::OnceLock::new();
+                    if let Some(__class) = __CLASS.get() {
+                        return Ok(__class.as_raw());
+                    }
+                    let __class = __jni_env.try_require_class_global(#class)?;
+                    Ok(__CLASS.get_or_init(|| __class).as_raw())
+                }
+            ));
+        } else {
+            contents.extend(quote!(
+                fn __class_global_ref(__jni_env: ::java_spaghetti::Env) -> ::java_spaghetti::sys::jobject {
+                    static __CLASS: ::std::sync::OnceLock<::java_spaghetti::Global<::java_spaghetti::JObject>> = ::std::sync::OnceLock::new();
+                    __CLASS.get_or_init(|| __jni_env.require_class_global(#class)).as_raw()
+                }
+            ));
+        }
 
         let mut methods: Vec<Method> = self
             .java
             .methods()
             .map(|m| Method::new(&self.java, m))
             .filter(|m| (m.java.is_public() || cc.include_private_methods) && !m.java.is_bridge())
+            .filter(|m| is_available(m.api_levels(context), cc.min_api_level))
             .collect();
         let mut fields: Vec<Field> = self
             .java
             .fields()
             .map(|f| Field::new(&self.java, f))
             .filter(|f| f.java.is_public() || cc.include_private_fields)
+            .filter(|f| is_available(f.api_levels(context), cc.min_api_level))
             .collect();
 
         self.resolve_collisions(&mut methods, &fields)?;
 
+        let names: HashSet<String> = self.fill_name_counts(&methods, &fields).into_keys().collect();
         for method in &mut methods {
+            method.resolve_variant_names(&cc, &names);
+        }
+        for field in &mut fields {
+            field.resolve_variant_names(&cc, &names);
+        }
+
+        let mut throws = TokenStream::new();
+        let mut emitted = Vec::with_capacity(methods.len());
+        for method in &mut methods {
+            method.resolve_throws_enum(context, &cc, &self.rust);
             let res = method.emit(context, &cc, &self.rust.mod_).unwrap();
+            if !res.is_empty() {
+                throws.extend(method.emit_throws(context, &self.rust)?);
+            }
+            emitted.push(!res.is_empty());
             contents.extend(res);
         }
 
@@ -189,15 +233,161 @@ impl Class {
             contents.extend(res);
         }
 
+        if self.is_iterable(context, &supertypes)
+            && !methods
+                .iter()
+                .any(|m| matches!(m.rust_name(), Some("iter" | "iter_as")))
+        {
+            // See `preamble-iter.rs`.
+            let root: TokenStream = self.rust.mod_.split("::").map(|_| quote!(super::)).collect();
+            contents.extend(quote!(
+                /// Iterates over the elements, with the `hasNext()` and `next()` of `iterator()`.
+                pub fn iter<'env>(self: &::java_spaghetti::Ref<'env, Self>) -> #root iter::Iter<'env, #object> {
+                    #root iter::Iter::new(self.upcast_ref())
+                }
+
+                /// Iterates over the elements like `iter()`, returning a `ClassCastException` for elements that
+                /// are not instances of `T`.
+                pub fn iter_as<'env, T: ::java_spaghetti::ReferenceType>(
+                    self: &::java_spaghetti::Ref<'env, Self>,
+                ) -> #root iter::Iter<'env, T> {
+                    #root iter::Iter::new(self.upcast_ref())
+                }
+            ));
+        }
+
+        let functional = self
+            .functional_method(context, &methods)
+            .filter(|&i| emitted[i])
+            .filter(|_| {
+                !methods
+                    .iter()
+                    .any(|m| matches!(m.rust_name(), Some("to_fn" | "from_fn")))
+            })
+            .map(|i| &methods[i]);
+        if let Some(method) = functional {
+            contents.extend(self.write_to_fn(context, method)?);
+        }
+
         out.extend(quote!(impl #rust_name { #contents }));
+        out.extend(throws);
 
         if cc.proxy {
-            out.extend(self.write_proxy(context, &methods)?);
+            out.extend(self.write_proxy(context, &methods, functional)?);
         }
 
         Ok(out)
     }
 
+    /// Returns the superclasses and superinterfaces of this class that are included in the bindings, recursively.
+    fn supertypes<'a>(&'a self, context: &'a Context) -> Vec<Id<'a>> {
+        let mut supertypes = Vec::new();
+        let mut visited = HashSet::from([self.java.path()]);
+        let mut queue = vec![&self.java];
+        while let Some(class) = queue.pop() {
+            for path in class.interfaces().map(|i| Id(i)).chain(class.super_path()) {
+                if let Some(superclass) = context.all_classes.get(path.as_str())
+                    && visited.insert(path)
+                {
+                    supertypes.push(path);
+                    queue.push(&superclass.java);
+                }
+            }
+        }
+        supertypes
+    }
+
+    /// Checks whether `iter()` can be emitted: the class is assignable to `java.lang.Iterable`, and the classes
+    /// used by the `iter` module of the preamble are included.
+    fn is_iterable(&self, context: &Context, supertypes: &[Id]) -> bool {
+        let iterable = Id("java/lang/Iterable");
+        !self.java.is_static()
+            && preamble::ITER
+                .iter()
+                .all(|class| context.all_classes.contains_key(*class))
+            && (self.java.path() == iterable || supertypes.contains(&iterable))
+    }
+
+    /// Returns the index in `methods` of the single abstract method of a functional interface, not counting the
+    /// public methods of `java.lang.Object`. Interfaces extending other interfaces only qualify if those are
+    /// included and have no abstract methods of their own.
+    fn functional_method(&self, context: &Context, methods: &[Method]) -> Option<usize> {
+        let abstract_methods = |class: &JavaClass| {
+            class
+                .methods()
+                .map(|m| Method::new(class, m))
+                .filter(|m| m.java.is_abstract() && !m.java.is_static() && !m.is_object_method())
+                .map(|m| (m.java.name().to_owned(), m.java.descriptor().to_string()))
+                .collect::<Vec<_>>()
+        };
+
+        if !self.java.is_interface() {
+            return None;
+        }
+        let mut queue = vec![&self.java];
+        while let Some(class) = queue.pop() {
+            for path in class.interfaces() {
+                let superinterface = &context.all_classes.get(&**path)?.java;
+                if !abstract_methods(superinterface).is_empty() {
+                    return None;
+                }
+                queue.push(superinterface);
+            }
+        }
+
+        let [(name, descriptor)] = &abstract_methods(&self.java)[..] else {
+            return None;
+        };
+        methods
+            .iter()
+            .position(|m| m.java.name() == *name && m.java.descriptor().to_string() == *descriptor)
+    }
+
+    /// Emits `to_fn()`, wrapping the single abstract method of a functional interface in a Rust closure.
+    fn write_to_fn(&self, context: &Context, method: &Method) -> anyhow::Result<TokenStream> {
+        let mut emit_reject_reasons = Vec::new();
+        let mut params = TokenStream::new();
+        let mut param_types = Vec::new();
+        let mut args = TokenStream::new();
+        for (arg_idx, arg) in method.java.descriptor.parameters.iter().enumerate() {
+            let arg_name = format_ident!("arg{}", arg_idx);
+            let arg_type = emit_type(
+                arg,
+                context,
+                &self.rust.mod_,
+                RustTypeFlavor::OptionBorrowedRef,
+                &mut emit_reject_reasons,
+            )?;
+            params.extend(quote!(#arg_name: #arg_type,));
+            param_types.push(arg_type);
+            args.extend(quote!(#arg_name,));
+        }
+        let ret = match &method.java.descriptor.return_type {
+            ReturnDescriptor::Void => quote!(()),
+            ReturnDescriptor::Return(desc) => emit_type(
+                desc,
+                context,
+                &self.rust.mod_,
+                RustTypeFlavor::OptionLocal,
+                &mut emit_reject_reasons,
+            )?,
+        };
+        let throwable = context.throwable_rust_path(&self.rust.mod_);
+        let rust_name = method.rust_name().unwrap();
+        let method_name = format_ident!("{rust_name}");
+        let docs = format!("Wraps [Self::{rust_name}] in a Rust closure.");
+
+        Ok(quote!(
+            #[doc = #docs]
+            pub fn to_fn<'env>(
+                self: &::java_spaghetti::Ref<'env, Self>,
+            ) -> impl Fn(#(#param_types),*) -> ::std::result::Result<#ret, ::java_spaghetti::Local<'env, #throwable>> + 'env {
+                let this = self.as_local();
+                move |#params| this.#method_name(#args).map_err(::std::convert::Into::into)
+            }
+        ))
+    }
+
     /// Fills the name_counts map with all field and method names
     fn fill_name_counts(&self, methods: &[Method], fields: &[Field]) -> std::collections::HashMap<String, usize> {
         use std::collections::HashMap;
@@ -280,3 +470,119 @@ impl Class {
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_classes::*;
+
+    #[test]
+    fn test_assignable_to_inherited_interfaces() {
+        let classes = [
+            TestClass::class("java/lang/Object"),
+            TestClass::class("java/lang/Throwable"),
+            TestClass::interface("java/lang/Iterable"),
+            TestClass::interface("java/util/Collection").implements("java/lang/Iterable"),
+            TestClass::interface("java/util/List").implements("java/util/Collection"),
+            TestClass::class("java/util/AbstractList").implements("java/util/List"),
+            TestClass::class("java/util/ArrayList")
+                .extends("java/util/AbstractList")
+                .implements("java/util/List"),
+        ];
+        let out = generate("", &classes);
+        let assignable = |class: &str, to: &str| {
+            out.contains(&compact(&format!(
+                "unsafe impl ::java_spaghetti::AssignableTo<{to}> for {class} {{}}"
+            )))
+        };
+
+        // Through the superclass `AbstractList`, and the superinterfaces `List` and `Collection`.
+        assert!(assignable("ArrayList", "AbstractList"));
+        assert!(assignable("ArrayList", "List"));
+        assert!(assignable("ArrayList", "Collection"));
+        assert!(assignable("ArrayList", "super::lang::Iterable"));
+        assert!(assignable("List", "Collection"));
+        assert!(assignable("List", "super::lang::Iterable"));
+    }
+
+    #[test]
+    fn test_try_variants_skip_existing_names() {
+        let classes = [
+            TestClass::class("java/lang/Object"),
+            TestClass::class("java/lang/Throwable"),
+            TestClass::class("com/example/Lock")
+                .method(ACC_PUBLIC, "lock", "()V")
+                .method(ACC_PUBLIC, "unlock", "()V")
+                .method(ACC_PUBLIC, "try_lock", "()Z")
+                .method(ACC_PUBLIC, "try_owner", "()I")
+                .field(ACC_PUBLIC, "owner", "I")
+                .field(ACC_PUBLIC, "count", "I"),
+        ];
+        let out = generate(
+            "rules:\n  - match: \"**\"\n    include: true\n    try_variants: true\n",
+            &classes,
+        );
+        let count = |name: &str| out.matches(&compact(&format!("pub fn {name}<"))).count();
+
+        // `try_lock()` and `try_owner()` keep their names, and the clashing variants are left out.
+        assert_eq!(count("try_lock"), 1);
+        assert!(out.contains(&compact(
+            "pub fn try_lock<'env>(self: &::java_spaghetti::Ref<'env, Self>,) -> ::std::result::Result<bool,"
+        )));
+        assert_eq!(count("try_owner"), 1);
+        assert!(out.contains(&compact(
+            "pub fn try_owner<'env>(self: &::java_spaghetti::Ref<'env, Self>,) -> ::std::result::Result<i32,"
+        )));
+
+        // The other variants are generated, including those of the clashing members that don't clash themselves.
+        assert_eq!(count("try_unlock"), 1);
+        assert_eq!(count("try_try_lock"), 1);
+        assert_eq!(count("try_set_owner"), 1);
+        assert_eq!(count("try_count"), 1);
+        assert_eq!(count("try_set_count"), 1);
+    }
+
+    #[test]
+    fn test_super_variants() {
+        let classes = [
+            TestClass::class("java/lang/Object"),
+            TestClass::class("java/lang/Throwable"),
+            TestClass::class("com/example/Base")
+                .access(ACC_PUBLIC | ACC_SUPER | ACC_ABSTRACT)
+                .method(ACC_PUBLIC, "<init>", "()V")
+                .method(ACC_PUBLIC, "run", "()V")
+                .method(ACC_PUBLIC, "stop", "()V")
+                .method(ACC_PUBLIC, "super_stop", "()V")
+                .method(ACC_PUBLIC | ACC_FINAL, "finalMethod", "()V")
+                .method(ACC_PRIVATE, "privateMethod", "()V")
+                .method(ACC_PUBLIC | ACC_STATIC, "staticMethod", "()V")
+                .method(ACC_PUBLIC | ACC_ABSTRACT, "abstractMethod", "()V"),
+            TestClass::class("com/example/Sealed")
+                .access(ACC_PUBLIC | ACC_SUPER | ACC_FINAL)
+                .method(ACC_PUBLIC, "run", "()V"),
+        ];
+        let out = generate(
+            "rules:\n  - match: \"**\"\n    include: true\n    include_private_methods: true\n    super_variants: true\n",
+            &classes,
+        );
+        let count = |name: &str| out.matches(&compact(&format!("pub fn {name}<"))).count();
+
+        // Only `Base.run()` is overridable.
+        assert_eq!(count("super_run"), 1);
+        assert!(out.contains(&compact(
+            "__jni_env.call_nonvirtual_void_method_a(self.as_raw(), __jni_class, __jni_method, __jni_args.as_ptr())"
+        )));
+        for name in [
+            "super_new",
+            "super_finalMethod",
+            "super_privateMethod",
+            "super_staticMethod",
+            "super_abstractMethod",
+        ] {
+            assert_eq!(count(name), 0, "{name}");
+        }
+
+        // `super_stop()` keeps its name, so `stop()` has no variant, but `super_stop()` does.
+        assert_eq!(count("super_stop"), 1);
+        assert_eq!(count("super_super_stop"), 1);
+    }
+}
@@ -1,11 +1,14 @@
+use std::collections::HashSet;
+
 use anyhow::anyhow;
 use cafebabe::constant_pool::LiteralConstant;
 use cafebabe::descriptors::{FieldDescriptor, FieldType};
 use proc_macro2::{Literal, TokenStream};
 use quote::{format_ident, quote};
 
-use super::cstring;
 use super::known_docs_url::KnownDocsUrl;
+use super::{api_levels_docs, cstring};
+use crate::api_versions::ApiLevels;
 use crate::config::ClassConfig;
 use crate::emit::Context;
 use crate::identifiers::{FieldMangling, mangle_field};
@@ -15,6 +18,8 @@ pub struct Field<'a> {
     pub class: &'a JavaClass,
     pub java: JavaField<'a>,
     pub rust_names: Result<FieldMangling<'a>, anyhow::Error>,
+    /// The names of the `try_` variants of the getter and setter, see [Field::resolve_variant_names].
+    try_names: (Option<String>, Option<String>),
 }
 
 impl<'a> Field<'a> {
@@ -23,9 +28,30 @@ impl<'a> Field<'a> {
             class,
             java: JavaField::from(java),
             rust_names: mangle_field(JavaField::from(java)),
+            try_names: (None, None),
         }
     }
 
+    pub fn api_levels(&self, context: &Context) -> Option<ApiLevels> {
+        context
+            .api_versions
+            .as_ref()?
+            .field(self.class.path().as_str(), self.java.name())
+    }
+
+    /// Names the `try_` variants of the getter and setter if enabled, unless a name is already used by a method or
+    /// field of the class (`names`), in which case that variant is left out.
+    pub fn resolve_variant_names(&mut self, cc: &ClassConfig, names: &HashSet<String>) {
+        let variant = |name: &str| {
+            let name = format!("try_{}", name.trim_start_matches("r#"));
+            (cc.try_variants && !names.contains(&name)).then_some(name)
+        };
+        self.try_names = match &self.rust_names {
+            Ok(FieldMangling::GetSet(get, set)) => (variant(get), variant(set)),
+            _ => (None, None),
+        };
+    }
+
     pub fn emit(&self, context: &Context, cc: &ClassConfig, mod_: &str) -> anyhow::Result<TokenStream> {
         let mut emit_reject_reasons = Vec::new();
 
@@ -97,6 +123,8 @@ impl<'a> Field<'a> {
             None => format!("{keywords} {}", self.java.name()),
         };
 
+        let api_docs = api_levels_docs(self.api_levels(context));
+
         match self.rust_names.as_ref().map_err(|e| anyhow!("bad mangling: {e}"))? {
             FieldMangling::ConstValue(constant, value) => {
                 let constant = format_ident!("{}", constant);
@@ -112,6 +140,7 @@ impl<'a> Field<'a> {
 
                 out.extend(quote!(
                     #[doc = #docs]
+                    #api_docs
                     #attributes
                     pub const #constant: #ty = #value;
                 ));
@@ -136,13 +165,22 @@ impl<'a> Field<'a> {
                 let java_name = cstring(self.java.name());
                 let descriptor = cstring(&self.java.descriptor().to_string());
 
+                // See `java_spaghetti::trace`.
+                let span = {
+                    let (class, name) = (self.class.path().as_str(), self.java.name());
+                    let descriptor = self.java.descriptor().to_string();
+                    quote!(let __jni_span = ::java_spaghetti::trace::enter(#class, #name, #descriptor);)
+                };
+
                 let get_docs = format!("**get** {docs}");
                 let set_docs = format!("**set** {docs}");
                 out.extend(quote!(
                     #[doc = #get_docs]
+                    #api_docs
                     #attributes
                     pub fn #get<'env>(#env_param) -> #rust_get_type {
                         static __FIELD: ::std::sync::OnceLock<::java_spaghetti::JFieldID> = ::std::sync::OnceLock::new();
+                        #span
                         #env_let
                         let __jni_class = Self::__class_global_ref(__jni_env);
                         unsafe {
@@ -162,9 +200,11 @@ impl<'a> Field<'a> {
 
                     out.extend(quote!(
                         #[doc = #set_docs]
+                        #api_docs
                         #attributes
                         pub fn #set<#lifetimes>(#env_param, value: #rust_set_type) {
                             static __FIELD: ::std::sync::OnceLock<::java_spaghetti::JFieldID> = ::std::sync::OnceLock::new();
+                            #span
                             #env_let
                             let __jni_class = Self::__class_global_ref(__jni_env);
                             unsafe {
@@ -174,6 +214,67 @@ impl<'a> Field<'a> {
                         }
                     ));
                 }
+
+                let (try_get, try_set) = &self.try_names;
+                if try_get.is_some() || try_set.is_some() {
+                    let try_require_field = format_ident!("try_{require_field}");
+                    // Only successful lookups are cached.
+                    let try_lookup = quote! {
+                        static __FIELD: ::std::sync::OnceLock<::java_spaghetti::JFieldID> = ::std::sync::OnceLock::new();
+                        #span
+                        #env_let
+                        let __jni_class = Self::__try_class_global_ref(__jni_env)?;
+                        let __jni_field = match __FIELD.get() {
+                            Some(__jni_field) => __jni_field,
+                            None => {
+                                let __jni_field = unsafe {
+                                    ::java_spaghetti::JFieldID::from_raw(
+                                        __jni_env.#try_require_field(__jni_class, #java_name, #descriptor)?
+                                    )
+                                };
+                                __FIELD.get_or_init(|| __jni_field)
+                            }
+                        }.as_raw();
+                    };
+
+                    if let Some(try_get) = try_get {
+                        let try_get = format_ident!("{try_get}");
+                        let try_get_docs = format!(
+                            "Like [Self::{get}], but returns a [LookupError](::java_spaghetti::LookupError) \
+                             instead of panicking if the class or field is not available."
+                        );
+                        out.extend(quote!(
+                            #[doc = #try_get_docs]
+                            #attributes
+                            pub fn #try_get<'env>(#env_param) -> ::std::result::Result<#rust_get_type, ::java_spaghetti::LookupError> {
+                                #try_lookup
+                                Ok(unsafe { __jni_env.#get_field(#this_or_class, __jni_field) })
+                            }
+                        ));
+                    }
+
+                    if let Some(try_set) = try_set.as_ref().filter(|_| !self.java.is_final()) {
+                        let lifetimes = if field_fragment == "object" {
+                            quote!('env, 'obj)
+                        } else {
+                            quote!('env)
+                        };
+                        let try_set = format_ident!("{try_set}");
+                        let try_set_docs = format!(
+                            "Like [Self::{set}], but returns a [LookupError](::java_spaghetti::LookupError) \
+                             instead of panicking if the class or field is not available."
+                        );
+                        out.extend(quote!(
+                            #[doc = #try_set_docs]
+                            #attributes
+                            pub fn #try_set<#lifetimes>(#env_param, value: #rust_set_type) -> ::std::result::Result<(), ::java_spaghetti::LookupError> {
+                                #try_lookup
+                                unsafe { __jni_env.#set_field(#this_or_class, __jni_field, value) };
+                                Ok(())
+                            }
+                        ));
+                    }
+                }
             }
         }
 
@@ -234,6 +335,7 @@ pub enum RustTypeFlavor {
     ImplAsArg,
     OptionLocal,
     OptionRef,
+    OptionBorrowedRef,
     Arg,
     Return,
 }
@@ -243,6 +345,7 @@ fn flavorify(ty: TokenStream, flavor: RustTypeFlavor) -> TokenStream {
         RustTypeFlavor::ImplAsArg => quote!(impl ::java_spaghetti::AsArg<#ty>),
         RustTypeFlavor::OptionLocal => quote!(::std::option::Option<::java_spaghetti::Local<'env, #ty>>),
         RustTypeFlavor::OptionRef => quote!(::std::option::Option<::java_spaghetti::Ref<'env, #ty>>),
+        RustTypeFlavor::OptionBorrowedRef => quote!(::std::option::Option<&::java_spaghetti::Ref<'env, #ty>>),
         RustTypeFlavor::Arg => quote!(::java_spaghetti::Arg<#ty>),
         RustTypeFlavor::Return => quote!(::java_spaghetti::Return<'env, #ty>),
     }
@@ -9,15 +9,33 @@ use crate::emit::Context;
 use crate::util;
 
 impl Class {
-    pub(crate) fn write_java_proxy(&self, context: &Context) -> anyhow::Result<String> {
-        // Collect methods for this class
-        let methods: Vec<Method> = self.java.methods().map(|m| Method::new(&self.java, m)).collect();
-
-        let java_proxy_path = format!(
+    /// JNI path of the Java proxy class, e.g. `java_spaghetti/proxy/java/lang/Runnable`.
+    pub(crate) fn java_proxy_path(&self, context: &Context) -> String {
+        format!(
             "{}/{}",
             context.config.proxy_package,
             self.java.path().as_str().replace("$", "_")
-        );
+        )
+    }
+
+    /// Methods overridden by the Java proxy class with a call to a native method.
+    pub(crate) fn java_proxy_methods(&self) -> Vec<Method<'_>> {
+        self.java
+            .methods()
+            .map(|m| Method::new(&self.java, m))
+            .filter(|method| {
+                method.rust_name().is_some()
+                    && !method.java.is_static()
+                    && !method.java.is_static_init()
+                    && !method.java.is_constructor()
+                    && !method.java.is_final()
+                    && !method.java.is_private()
+            })
+            .collect()
+    }
+
+    pub(crate) fn write_java_proxy(&self, context: &Context) -> anyhow::Result<String> {
+        let java_proxy_path = self.java_proxy_path(context);
 
         let package_name = java_proxy_path.rsplit_once('/').map(|x| x.0).unwrap_or("");
         let class_name = java_proxy_path.split('/').next_back().unwrap();
@@ -66,17 +84,7 @@ impl Class {
         writeln!(w)?;
 
         // Generate methods
-        for method in methods {
-            let Some(_rust_name) = method.rust_name() else { continue };
-            if method.java.is_static()
-                || method.java.is_static_init()
-                || method.java.is_constructor()
-                || method.java.is_final()
-                || method.java.is_private()
-            {
-                continue;
-            }
-
+        for method in self.java_proxy_methods() {
             let method_name = method.java.name();
 
             // Method signature
@@ -170,7 +178,7 @@ fn java_type_name(desc: &FieldDescriptor) -> anyhow::Result<String> {
 }
 
 pub fn write_java_proxy_files(context: &Context, output_dir: &Path) -> anyhow::Result<()> {
-    for (_, class) in context.all_classes.iter() {
+    for class in context.all_classes.values() {
         let cc = context.config.resolve_class(class.java.path().as_str());
         if !cc.proxy {
             continue;
@@ -0,0 +1,455 @@
+//! Minimal class file writer for the Java proxy classes, so that they can be embedded in the generated bindings
+//! and defined at runtime with JNI `DefineClass`, without compiling the `.java` files from `java_proxy.rs`.
+//!
+//! The written classes have the same shape as the Java sources:
+//!
+//! ```java
+//! class Proxy extends Base /* or: implements Base */ {
+//!     long ptr;
+//!     private Proxy(long ptr) { this.ptr = ptr; }
+//!     protected void finalize() throws Throwable { native_finalize(this.ptr); }
+//!     private native void native_finalize(long ptr);
+//!     public R method(A arg0) { return native_method(ptr, arg0); }
+//!     private native R native_method(long ptr, A arg0);
+//! }
+//! ```
+
+use std::collections::HashMap;
+
+use cafebabe::descriptors::{FieldDescriptor, FieldType, MethodDescriptor, ReturnDescriptor};
+
+use super::classes::Class;
+use crate::emit::Context;
+
+const ACC_PUBLIC: u16 = 0x0001;
+const ACC_PRIVATE: u16 = 0x0002;
+const ACC_PROTECTED: u16 = 0x0004;
+const ACC_SUPER: u16 = 0x0020;
+const ACC_NATIVE: u16 = 0x0100;
+
+// Java 8. The code has no branches, so no `StackMapTable` attributes are needed.
+const MAJOR_VERSION: u16 = 52;
+
+impl Class {
+    pub(crate) fn write_java_proxy_bytecode(&self, context: &Context) -> anyhow::Result<Vec<u8>> {
+        let base = self.java.path().as_str();
+        let (super_class, interfaces) = match self.java.is_interface() {
+            true => ("java/lang/Object", vec![base]),
+            false => (base, vec![]),
+        };
+        let methods = self.java_proxy_methods();
+        let methods: Vec<_> = methods.iter().map(|m| (m.java.name(), m.java.descriptor())).collect();
+        Ok(write_proxy_class(
+            &self.java_proxy_path(context),
+            super_class,
+            &interfaces,
+            &methods,
+        ))
+    }
+}
+
+fn write_proxy_class(
+    this_class: &str,
+    super_class: &str,
+    interfaces: &[&str],
+    methods: &[(&str, &MethodDescriptor)],
+) -> Vec<u8> {
+    let mut cp = ConstantPool::default();
+    let this_index = cp.class(this_class);
+    let super_index = cp.class(super_class);
+    let interface_indices: Vec<u16> = interfaces.iter().map(|i| cp.class(i)).collect();
+    let ptr_field = cp.fieldref(this_class, "ptr", "J");
+
+    let mut out_methods = Vec::new();
+    let mut method_count = 0u16;
+
+    // private Proxy(long ptr) { super(); this.ptr = ptr; }
+    let super_init = cp.methodref(super_class, "<init>", "()V");
+    let mut code = vec![ALOAD_0, INVOKESPECIAL];
+    code.extend(super_init.to_be_bytes());
+    code.extend([ALOAD_0, LLOAD_1, PUTFIELD]);
+    code.extend(ptr_field.to_be_bytes());
+    code.push(RETURN);
+    write_method(
+        &mut out_methods,
+        &mut cp,
+        ACC_PRIVATE,
+        "<init>",
+        "(J)V",
+        Some((3, 3, &code)),
+    );
+    method_count += 1;
+
+    // protected void finalize() throws Throwable { native_finalize(this.ptr); }
+    let native_finalize = cp.methodref(this_class, "native_finalize", "(J)V");
+    let mut code = vec![ALOAD_0, ALOAD_0, GETFIELD];
+    code.extend(ptr_field.to_be_bytes());
+    code.push(INVOKESPECIAL);
+    code.extend(native_finalize.to_be_bytes());
+    code.push(RETURN);
+    write_method(
+        &mut out_methods,
+        &mut cp,
+        ACC_PROTECTED,
+        "finalize",
+        "()V",
+        Some((3, 1, &code)),
+    );
+    write_method(
+        &mut out_methods,
+        &mut cp,
+        ACC_PRIVATE | ACC_NATIVE,
+        "native_finalize",
+        "(J)V",
+        None,
+    );
+    method_count += 2;
+
+    for (name, descriptor) in methods {
+        let descriptor_str = descriptor.to_string();
+        let native_name = format!("native_{name}");
+        let native_descriptor = format!("(J{}", &descriptor_str[1..]);
+        let native_method = cp.methodref(this_class, &native_name, &native_descriptor);
+
+        // public R method(A arg0) { return native_method(ptr, arg0); }
+        let mut code = vec![ALOAD_0, ALOAD_0, GETFIELD];
+        code.extend(ptr_field.to_be_bytes());
+        let mut slot = 1u16;
+        for param in &descriptor.parameters {
+            let (load, size) = load_opcode(param);
+            code.extend([load, slot as u8]);
+            slot += size;
+        }
+        code.push(INVOKESPECIAL);
+        code.extend(native_method.to_be_bytes());
+        code.push(match &descriptor.return_type {
+            ReturnDescriptor::Void => RETURN,
+            ReturnDescriptor::Return(desc) => return_opcode(desc),
+        });
+        // `this` + `ptr` (2 slots) + the arguments are pushed onto the stack.
+        let max_stack = slot + 2;
+        write_method(
+            &mut out_methods,
+            &mut cp,
+            ACC_PUBLIC,
+            name,
+            &descriptor_str,
+            Some((max_stack, slot, &code)),
+        );
+        write_method(
+            &mut out_methods,
+            &mut cp,
+            ACC_PRIVATE | ACC_NATIVE,
+            &native_name,
+            &native_descriptor,
+            None,
+        );
+        method_count += 2;
+    }
+
+    let ptr_name = cp.utf8("ptr");
+    let ptr_descriptor = cp.utf8("J");
+
+    let mut out = Vec::new();
+    out.extend(0xCAFEBABEu32.to_be_bytes());
+    out.extend(0u16.to_be_bytes());
+    out.extend(MAJOR_VERSION.to_be_bytes());
+    out.extend(cp.count.to_be_bytes());
+    out.extend(&cp.bytes);
+    out.extend(ACC_SUPER.to_be_bytes());
+    out.extend(this_index.to_be_bytes());
+    out.extend(super_index.to_be_bytes());
+    out.extend((interface_indices.len() as u16).to_be_bytes());
+    for index in interface_indices {
+        out.extend(index.to_be_bytes());
+    }
+    // long ptr;
+    out.extend(1u16.to_be_bytes());
+    out.extend(0u16.to_be_bytes());
+    out.extend(ptr_name.to_be_bytes());
+    out.extend(ptr_descriptor.to_be_bytes());
+    out.extend(0u16.to_be_bytes());
+    // methods
+    out.extend(method_count.to_be_bytes());
+    out.extend(out_methods);
+    // class attributes
+    out.extend(0u16.to_be_bytes());
+    out
+}
+
+/// Appends a `method_info`, with a `Code` attribute of `(max_stack, max_locals, code)` if not native.
+fn write_method(
+    out: &mut Vec<u8>,
+    cp: &mut ConstantPool,
+    access: u16,
+    name: &str,
+    descriptor: &str,
+    code: Option<(u16, u16, &[u8])>,
+) {
+    out.extend(access.to_be_bytes());
+    out.extend(cp.utf8(name).to_be_bytes());
+    out.extend(cp.utf8(descriptor).to_be_bytes());
+    match code {
+        None => out.extend(0u16.to_be_bytes()),
+        Some((max_stack, max_locals, code)) => {
+            out.extend(1u16.to_be_bytes());
+            out.extend(cp.utf8("Code").to_be_bytes());
+            out.extend((12 + code.len() as u32).to_be_bytes());
+            out.extend(max_stack.to_be_bytes());
+            out.extend(max_locals.to_be_bytes());
+            out.extend((code.len() as u32).to_be_bytes());
+            out.extend(code);
+            out.extend(0u16.to_be_bytes()); // exception_table_length
+            out.extend(0u16.to_be_bytes()); // attributes_count
+        }
+    }
+}
+
+const ALOAD_0: u8 = 0x2a;
+const LLOAD_1: u8 = 0x1f;
+const ILOAD: u8 = 0x15;
+const LLOAD: u8 = 0x16;
+const FLOAD: u8 = 0x17;
+const DLOAD: u8 = 0x18;
+const ALOAD: u8 = 0x19;
+const IRETURN: u8 = 0xac;
+const LRETURN: u8 = 0xad;
+const FRETURN: u8 = 0xae;
+const DRETURN: u8 = 0xaf;
+const ARETURN: u8 = 0xb0;
+const RETURN: u8 = 0xb1;
+const GETFIELD: u8 = 0xb4;
+const PUTFIELD: u8 = 0xb5;
+const INVOKESPECIAL: u8 = 0xb7;
+
+/// Returns the load instruction for a local variable of this type, and the number of slots it takes.
+fn load_opcode(desc: &FieldDescriptor) -> (u8, u16) {
+    if desc.dimensions > 0 {
+        return (ALOAD, 1);
+    }
+    match desc.field_type {
+        FieldType::Long => (LLOAD, 2),
+        FieldType::Double => (DLOAD, 2),
+        FieldType::Float => (FLOAD, 1),
+        FieldType::Object(_) => (ALOAD, 1),
+        FieldType::Boolean | FieldType::Byte | FieldType::Char | FieldType::Short | FieldType::Integer => (ILOAD, 1),
+    }
+}
+
+fn return_opcode(desc: &FieldDescriptor) -> u8 {
+    match load_opcode(desc).0 {
+        LLOAD => LRETURN,
+        DLOAD => DRETURN,
+        FLOAD => FRETURN,
+        ALOAD => ARETURN,
+        _ => IRETURN,
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum Constant {
+    Utf8(String),
+    Class(u16),
+    NameAndType(u16, u16),
+    Fieldref(u16, u16),
+    Methodref(u16, u16),
+}
+
+/// Deduplicating constant pool, written out as it grows.
+pub(super) struct ConstantPool {
+    /// The `constant_pool_count`, one more than the number of entries.
+    pub(super) count: u16,
+    pub(super) bytes: Vec<u8>,
+    indices: HashMap<Constant, u16>,
+}
+
+impl Default for ConstantPool {
+    fn default() -> Self {
+        Self {
+            count: 1,
+            bytes: Vec::new(),
+            indices: HashMap::new(),
+        }
+    }
+}
+
+impl ConstantPool {
+    fn add(&mut self, constant: Constant) -> u16 {
+        if let Some(&index) = self.indices.get(&constant) {
+            return index;
+        }
+        match &constant {
+            Constant::Utf8(s) => {
+                let s = modified_utf8(s);
+                self.bytes.push(1);
+                self.bytes.extend((s.len() as u16).to_be_bytes());
+                self.bytes.extend(s);
+            }
+            Constant::Class(name) => {
+                self.bytes.push(7);
+                self.bytes.extend(name.to_be_bytes());
+            }
+            Constant::NameAndType(name, descriptor) => {
+                self.bytes.push(12);
+                self.bytes.extend(name.to_be_bytes());
+                self.bytes.extend(descriptor.to_be_bytes());
+            }
+            Constant::Fieldref(class, name_and_type) => {
+                self.bytes.push(9);
+                self.bytes.extend(class.to_be_bytes());
+                self.bytes.extend(name_and_type.to_be_bytes());
+            }
+            Constant::Methodref(class, name_and_type) => {
+                self.bytes.push(10);
+                self.bytes.extend(class.to_be_bytes());
+                self.bytes.extend(name_and_type.to_be_bytes());
+            }
+        }
+        let index = self.count;
+        self.count += 1;
+        self.indices.insert(constant, index);
+        index
+    }
+
+    pub(super) fn utf8(&mut self, s: &str) -> u16 {
+        self.add(Constant::Utf8(s.to_owned()))
+    }
+
+    pub(super) fn class(&mut self, name: &str) -> u16 {
+        let name = self.utf8(name);
+        self.add(Constant::Class(name))
+    }
+
+    fn name_and_type(&mut self, name: &str, descriptor: &str) -> u16 {
+        let name = self.utf8(name);
+        let descriptor = self.utf8(descriptor);
+        self.add(Constant::NameAndType(name, descriptor))
+    }
+
+    fn fieldref(&mut self, class: &str, name: &str, descriptor: &str) -> u16 {
+        let class = self.class(class);
+        let name_and_type = self.name_and_type(name, descriptor);
+        self.add(Constant::Fieldref(class, name_and_type))
+    }
+
+    fn methodref(&mut self, class: &str, name: &str, descriptor: &str) -> u16 {
+        let class = self.class(class);
+        let name_and_type = self.name_and_type(name, descriptor);
+        self.add(Constant::Methodref(class, name_and_type))
+    }
+}
+
+/// Encodes `s` in the "Modified UTF-8" used by class files: NUL takes two bytes, and supplementary characters
+/// are written as two three-byte surrogates.
+fn modified_utf8(s: &str) -> Vec<u8> {
+    let mut out = Vec::with_capacity(s.len());
+    for unit in s.encode_utf16() {
+        match unit {
+            0x0001..=0x007F => out.push(unit as u8),
+            0x0000 | 0x0080..=0x07FF => {
+                out.push(0xC0 | (unit >> 6) as u8);
+                out.push(0x80 | (unit & 0x3F) as u8);
+            }
+            _ => {
+                out.push(0xE0 | (unit >> 12) as u8);
+                out.push(0x80 | ((unit >> 6) & 0x3F) as u8);
+                out.push(0x80 | (unit & 0x3F) as u8);
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use cafebabe::attributes::AttributeData;
+    use cafebabe::bytecode::Opcode;
+    use cafebabe::{MethodAccessFlags, ParseOptions};
+
+    use super::*;
+
+    #[test]
+    fn modified_utf8_encoding() {
+        assert_eq!(modified_utf8("abc"), b"abc");
+        assert_eq!(modified_utf8("a\0b"), b"a\xC0\x80b");
+        assert_eq!(modified_utf8("é"), "é".as_bytes());
+        assert_eq!(modified_utf8("😀"), b"\xED\xA0\xBD\xED\xB8\x80");
+    }
+
+    #[test]
+    fn proxy_class_parses() {
+        let descriptor = MethodDescriptor {
+            parameters: vec![
+                FieldDescriptor {
+                    dimensions: 0,
+                    field_type: FieldType::Double,
+                },
+                FieldDescriptor {
+                    dimensions: 1,
+                    field_type: FieldType::Integer,
+                },
+            ],
+            return_type: ReturnDescriptor::Return(FieldDescriptor {
+                dimensions: 0,
+                field_type: FieldType::Long,
+            }),
+        };
+        let bytes = write_proxy_class(
+            "java_spaghetti/proxy/com/example/Foo",
+            "java/lang/Object",
+            &["com/example/Foo"],
+            &[("bar", &descriptor)],
+        );
+
+        let mut options = ParseOptions::default();
+        options.parse_bytecode(true);
+        let class = cafebabe::parse_class_with_options(&bytes, &options).unwrap();
+        assert_eq!(class.major_version, MAJOR_VERSION);
+        assert_eq!(&*class.this_class, "java_spaghetti/proxy/com/example/Foo");
+        assert_eq!(class.super_class.as_deref(), Some("java/lang/Object"));
+        assert_eq!(class.interfaces.len(), 1);
+        assert_eq!(class.fields.len(), 1);
+
+        let methods: Vec<_> = class
+            .methods
+            .iter()
+            .map(|m| (m.name.as_ref(), m.descriptor.to_string()))
+            .collect();
+        assert_eq!(
+            methods,
+            [
+                ("<init>", "(J)V".to_string()),
+                ("finalize", "()V".to_string()),
+                ("native_finalize", "(J)V".to_string()),
+                ("bar", "(D[I)J".to_string()),
+                ("native_bar", "(JD[I)J".to_string()),
+            ]
+        );
+        assert!(class.methods[4].access_flags.contains(MethodAccessFlags::NATIVE));
+
+        let AttributeData::Code(code) = &class.methods[3].attributes[0].data else {
+            panic!("expected a Code attribute");
+        };
+        assert_eq!((code.max_stack, code.max_locals), (6, 4));
+        let opcodes: Vec<_> = code
+            .bytecode
+            .as_ref()
+            .unwrap()
+            .opcodes
+            .iter()
+            .map(|(_, op)| op)
+            .collect();
+        assert!(matches!(
+            opcodes[..],
+            [
+                Opcode::Aload(0),
+                Opcode::Aload(0),
+                Opcode::Getfield(_),
+                Opcode::Dload(1),
+                Opcode::Aload(3),
+                Opcode::Invokespecial(_),
+                Opcode::Lreturn,
+            ]
+        ));
+    }
+}
@@ -13,7 +13,7 @@ pub(crate) struct KnownDocsUrl {
 
 impl Display for KnownDocsUrl {
     fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
-        write!(fmt, "[{}]({})", &self.label, &self.url)
+        write!(fmt, "[{}]({})", self.label, self.url)
     }
 }
 
@@ -1,20 +1,30 @@
+use std::collections::HashSet;
+
 use cafebabe::descriptors::ReturnDescriptor;
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
 
-use super::cstring;
+use super::classes::{Class, StructPaths};
 use super::fields::{RustTypeFlavor, emit_fragment_type, emit_type};
 use super::known_docs_url::KnownDocsUrl;
+use super::{api_levels_docs, cstring};
+use crate::api_versions::ApiLevels;
 use crate::config::ClassConfig;
 use crate::emit::Context;
 use crate::identifiers::MethodManglingStyle;
-use crate::parser_util::{JavaClass, JavaMethod};
+use crate::parser_util::{Id, JavaClass, JavaMethod};
 
 pub struct Method<'a> {
     pub class: &'a JavaClass,
     pub java: JavaMethod<'a>,
     rust_name: Option<String>,
     mangling_style: MethodManglingStyle,
+    /// The enum of declared exceptions returned as the error, see [Method::resolve_throws_enum].
+    throws_enum: Option<String>,
+    /// The name of the `try_` variant, see [Method::resolve_variant_names].
+    try_name: Option<String>,
+    /// The name of the `super_` variant, see [Method::resolve_variant_names].
+    super_name: Option<String>,
 }
 
 impl<'a> Method<'a> {
@@ -24,6 +34,9 @@ impl<'a> Method<'a> {
             java: JavaMethod::from(java),
             rust_name: None,
             mangling_style: MethodManglingStyle::Java,
+            throws_enum: None,
+            try_name: None,
+            super_name: None,
         };
         result.set_mangling_style(MethodManglingStyle::Java);
         result
@@ -41,6 +54,23 @@ impl<'a> Method<'a> {
             .ok()
     }
 
+    /// Whether this is `equals()`, `hashCode()` or `toString()` of `java.lang.Object`, which interfaces may redeclare
+    /// as abstract methods to document them.
+    pub fn is_object_method(&self) -> bool {
+        matches!(
+            (self.java.name(), self.java.descriptor().to_string().as_str()),
+            ("equals", "(Ljava/lang/Object;)Z") | ("hashCode", "()I") | ("toString", "()Ljava/lang/String;")
+        )
+    }
+
+    pub fn api_levels(&self, context: &Context) -> Option<ApiLevels> {
+        context.api_versions.as_ref()?.method(
+            self.class.path().as_str(),
+            self.java.name(),
+            &self.java.descriptor().to_string(),
+        )
+    }
+
     pub fn emit(&self, context: &Context, cc: &ClassConfig, mod_: &str) -> anyhow::Result<TokenStream> {
         let mut emit_reject_reasons = Vec::new();
 
@@ -125,21 +155,47 @@ impl<'a> Method<'a> {
             None => self.java.name().to_string(),
         };
 
+        let api_docs = api_levels_docs(self.api_levels(context));
+
         let throwable = context.throwable_rust_path(mod_);
 
+        let (throws_docs, error) = match &self.throws_enum {
+            Some(name) => {
+                let docs = format!("\n\nDeclared exceptions are returned as variants of [{name}].");
+                let name = format_ident!("{name}");
+                (quote!(#[doc = #docs]), quote!(#name<'env>))
+            }
+            None => (quote!(), quote!(::java_spaghetti::Local<'env, #throwable>)),
+        };
+        // The calls of `Env` return a `Local<Throwable>` error, converted to the enum of declared exceptions.
+        let convert_error = |call: TokenStream| match &self.throws_enum {
+            Some(_) => quote!({
+                let __jni_result: ::std::result::Result<_, ::java_spaghetti::Local<'env, #throwable>> = #call;
+                __jni_result.map_err(::std::convert::From::from)
+            }),
+            None => call,
+        };
+
         let env_let = match !self.java.is_constructor() && !self.java.is_static() {
             true => quote!(let __jni_env = self.env();),
             false => quote!(),
         };
         let require_method = match self.java.is_static() {
-            false => quote!(require_method),
-            true => quote!(require_static_method),
+            false => format_ident!("require_method"),
+            true => format_ident!("require_static_method"),
         };
 
         let java_name = cstring(self.java.name());
         let descriptor = cstring(&self.java.descriptor().to_string());
         let method_name = format_ident!("{method_name}");
 
+        // See `java_spaghetti::trace`.
+        let span = {
+            let (class, name) = (self.class.path().as_str(), self.java.name());
+            let descriptor = self.java.descriptor().to_string();
+            quote!(let __jni_span = ::java_spaghetti::trace::enter(#class, #name, #descriptor);)
+        };
+
         let call = if self.java.is_constructor() {
             quote!(__jni_env.new_object_a(__jni_class, __jni_method, __jni_args.as_ptr()))
         } else if self.java.is_static() {
@@ -149,12 +205,16 @@ impl<'a> Method<'a> {
             let call = format_ident!("call_{ret_method_fragment}_method_a");
             quote!(    __jni_env.#call(self.as_raw(), __jni_method, __jni_args.as_ptr()))
         };
+        let call = convert_error(call);
 
         out.extend(quote!(
             #[doc = #docs]
+            #api_docs
+            #throws_docs
             #attributes
-            pub fn #method_name<'env>(#params_decl) -> ::std::result::Result<#ret_decl, ::java_spaghetti::Local<'env, #throwable>> {
+            pub fn #method_name<'env>(#params_decl) -> ::std::result::Result<#ret_decl, #error> {
                 static __METHOD: ::std::sync::OnceLock<::java_spaghetti::JMethodID> = ::std::sync::OnceLock::new();
+                #span
                 unsafe {
                     let __jni_args = [#params_array];
                     #env_let
@@ -168,6 +228,392 @@ impl<'a> Method<'a> {
             }
         ));
 
+        if let Some(super_method_name) = &self.super_name {
+            let super_docs =
+                format!("Like [Self::{method_name}], but calls this class's implementation even if it is overridden.");
+            let super_method_name = format_ident!("{super_method_name}");
+            let call = format_ident!("call_nonvirtual_{ret_method_fragment}_method_a");
+            let call =
+                convert_error(quote!(__jni_env.#call(self.as_raw(), __jni_class, __jni_method, __jni_args.as_ptr())));
+            out.extend(quote!(
+                #[doc = #super_docs]
+                #api_docs
+                #throws_docs
+                #attributes
+                pub fn #super_method_name<'env>(#params_decl) -> ::std::result::Result<#ret_decl, #error> {
+                    static __METHOD: ::std::sync::OnceLock<::java_spaghetti::JMethodID> = ::std::sync::OnceLock::new();
+                    #span
+                    unsafe {
+                        let __jni_args = [#params_array];
+                        let __jni_env = self.env();
+                        let __jni_class = Self::__class_global_ref(__jni_env);
+                        let __jni_method = __METHOD.get_or_init(||
+                            ::java_spaghetti::JMethodID::from_raw(__jni_env.#require_method(__jni_class, #java_name, #descriptor))
+                        ).as_raw();
+
+                        #call
+                    }
+                }
+            ));
+        }
+
+        if let Some(try_method_name) = &self.try_name {
+            let try_docs = format!(
+                "Like [Self::{method_name}], but returns a [LookupError](::java_spaghetti::LookupError) \
+                 instead of panicking if the class or method is not available."
+            );
+            let try_method_name = format_ident!("{try_method_name}");
+            let try_require_method = format_ident!("try_{require_method}");
+            out.extend(quote!(
+                #[doc = #try_docs]
+                #api_docs
+                #throws_docs
+                #attributes
+                pub fn #try_method_name<'env>(#params_decl) -> ::std::result::Result<
+                    ::std::result::Result<#ret_decl, #error>,
+                    ::java_spaghetti::LookupError,
+                > {
+                    static __METHOD: ::std::sync::OnceLock<::java_spaghetti::JMethodID> = ::std::sync::OnceLock::new();
+                    #span
+                    unsafe {
+                        let __jni_args = [#params_array];
+                        #env_let
+                        let __jni_class = Self::__try_class_global_ref(__jni_env)?;
+                        let __jni_method = match __METHOD.get() {
+                            Some(__jni_method) => __jni_method,
+                            None => {
+                                let __jni_method = ::java_spaghetti::JMethodID::from_raw(
+                                    __jni_env.#try_require_method(__jni_class, #java_name, #descriptor)?
+                                );
+                                __METHOD.get_or_init(|| __jni_method)
+                            }
+                        }.as_raw();
+
+                        Ok(#call)
+                    }
+                }
+            ));
+        }
+
         Ok(out)
     }
+
+    /// Included exception classes from the `throws` clause, ordered so subclasses come before their superclasses.
+    /// `java.lang.Throwable` itself is left out, as it's the error type of every method already.
+    fn declared_exceptions(&self, context: &Context) -> Vec<Id<'a>> {
+        let throwable = Id("java/lang/Throwable");
+        let mut exceptions: Vec<Id<'a>> = Vec::new();
+        for exception in self.java.exceptions() {
+            if exception == throwable
+                || exceptions.contains(&exception)
+                || !context.all_classes.contains_key(exception.as_str())
+                || !context.is_subclass_of(exception, throwable)
+            {
+                continue;
+            }
+            let pos = exceptions
+                .iter()
+                .position(|e| context.is_subclass_of(exception, *e))
+                .unwrap_or(exceptions.len());
+            exceptions.insert(pos, exception);
+        }
+        exceptions
+    }
+
+    /// Names the `try_` and `super_` variants if enabled, unless the name is already used by a method or field of the
+    /// class (`names`), in which case the variant is left out. `super_` variants are only for methods that can be
+    /// overridden: instance methods with a body, neither private nor final, of a class that isn't final.
+    pub fn resolve_variant_names(&mut self, cc: &ClassConfig, names: &HashSet<String>) {
+        let overridable = !self.class.is_final()
+            && !self.java.is_private()
+            && !self.java.is_final()
+            && !self.java.is_static()
+            && !self.java.is_abstract()
+            && !self.java.is_constructor();
+        let rust_name = self.rust_name().map(|name| name.trim_start_matches("r#").to_owned());
+        let variant = |enabled: bool, prefix: &str| {
+            let name = format!("{prefix}{}", rust_name.as_ref()?);
+            (enabled && !names.contains(&name)).then_some(name)
+        };
+        self.try_name = variant(cc.try_variants, "try_");
+        self.super_name = variant(cc.super_variants && overridable, "super_");
+    }
+
+    /// Picks the name of the enum of declared exceptions, `{Class}{Method}Error`, if `typed_throws` is enabled and the
+    /// method declares included exceptions. The enum is left out if its name is already used in the module, e.g. by a
+    /// class.
+    pub fn resolve_throws_enum(&mut self, context: &Context, cc: &ClassConfig, rust: &StructPaths) {
+        self.throws_enum = None;
+        let Some(method_name) = self.rust_name().filter(|_| cc.typed_throws) else {
+            return;
+        };
+        if self.declared_exceptions(context).is_empty() {
+            return;
+        }
+        let name = format!("{}{}Error", camel_case(&rust.struct_name), camel_case(method_name));
+        if context.claim_item_name(&rust.mod_, &name) {
+            self.throws_enum = Some(name);
+        }
+    }
+
+    /// Emits an enum of the declared exceptions, with a `From<Local<Throwable>>` impl downcasting to them in turn, and
+    /// `Debug` and `Display` impls formatting the exception as a `Throwable`. Should only be called for methods that
+    /// [Method::emit] didn't reject.
+    pub fn emit_throws(&self, context: &Context, rust: &StructPaths) -> anyhow::Result<TokenStream> {
+        let Some(enum_name) = &self.throws_enum else {
+            return Ok(TokenStream::new());
+        };
+        let enum_name = format_ident!("{enum_name}");
+        let throwable = context.throwable_rust_path(&rust.mod_);
+        let method_name = self.rust_name().unwrap();
+
+        let docs = format!(
+            "Exceptions declared by [{}::{method_name}], returned as its error.",
+            rust.struct_name
+        );
+
+        let mut variants = TokenStream::new();
+        let mut downcasts = TokenStream::new();
+        let mut upcasts = TokenStream::new();
+        let mut upcast_refs = TokenStream::new();
+        let mut variant_names = Vec::new();
+        for exception in self.declared_exceptions(context) {
+            let mut variant_name = camel_case(&Class::name_for(exception)?);
+            if variant_names.contains(&variant_name) || variant_name == "Other" {
+                variant_name = camel_case(&format!(
+                    "{}_{variant_name}",
+                    Class::mod_for(exception)?.replace("::", "_")
+                ));
+            }
+            let variant = format_ident!("{variant_name}");
+            let path = context.java_to_rust_path(exception, &rust.mod_)?;
+            variants.extend(quote!(#variant(::java_spaghetti::Local<'env, #path>),));
+            downcasts.extend(quote!(
+                let e = match e.downcast::<#path>() {
+                    Ok(e) => return Self::#variant(e),
+                    Err(e) => e,
+                };
+            ));
+            upcasts.extend(quote!(#enum_name::#variant(e) => e.upcast(),));
+            upcast_refs.extend(quote!(Self::#variant(e) => e.upcast_ref(),));
+            variant_names.push(variant_name);
+        }
+
+        Ok(quote!(
+            #[doc = #docs]
+            pub enum #enum_name<'env> {
+                #variants
+                /// An exception not declared by the method, e.g. a `RuntimeException`.
+                Other(::java_spaghetti::Local<'env, #throwable>),
+            }
+
+            impl<'env> ::std::convert::From<::java_spaghetti::Local<'env, #throwable>> for #enum_name<'env> {
+                fn from(e: ::java_spaghetti::Local<'env, #throwable>) -> Self {
+                    #downcasts
+                    Self::Other(e)
+                }
+            }
+
+            impl<'env> ::std::convert::From<#enum_name<'env>> for ::java_spaghetti::Local<'env, #throwable> {
+                fn from(e: #enum_name<'env>) -> Self {
+                    match e {
+                        #upcasts
+                        #enum_name::Other(e) => e,
+                    }
+                }
+            }
+
+            impl<'env> #enum_name<'env> {
+                /// Returns the exception as a `Throwable`, whichever variant it is.
+                pub fn as_throwable(&self) -> &::java_spaghetti::Ref<'env, #throwable> {
+                    match self {
+                        #upcast_refs
+                        Self::Other(e) => e,
+                    }
+                }
+            }
+
+            impl<'env> ::std::fmt::Debug for #enum_name<'env> {
+                fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                    ::std::fmt::Debug::fmt(self.as_throwable(), f)
+                }
+            }
+
+            /// Formats the exception with its `toString()`, e.g. `java.io.IOException: message`.
+            impl<'env> ::std::fmt::Display for #enum_name<'env> {
+                fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                    match self.as_throwable().toString() {
+                        Ok(Some(s)) => f.write_str(&s.to_string_lossy()),
+                        _ => f.write_str("N/A (toString returned null or threw an exception)"),
+                    }
+                }
+            }
+        ))
+    }
+}
+
+/// Turns a Rust identifier like `read_all`, `readAll` or `Map_Entry` into CamelCase, like `ReadAll` or `MapEntry`.
+fn camel_case(name: &str) -> String {
+    name.trim_start_matches("r#")
+        .split('_')
+        .flat_map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase())
+                .into_iter()
+                .chain(chars)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_classes::*;
+
+    const TYPED_THROWS: &str = "rules:\n  - match: \"**\"\n    include: true\n    typed_throws: true\n";
+
+    fn throwables() -> Vec<TestClass> {
+        vec![
+            TestClass::class("java/lang/Object"),
+            TestClass::class("java/lang/Throwable"),
+            TestClass::class("java/lang/Exception").extends("java/lang/Throwable"),
+            TestClass::class("java/io/IOException").extends("java/lang/Exception"),
+        ]
+    }
+
+    #[test]
+    fn test_declared_exceptions_enum() {
+        let mut classes = throwables();
+        classes.push(
+            TestClass::class("com/example/Foo")
+                .method(ACC_PUBLIC, "read", "()I")
+                .throws("java/io/IOException")
+                .method(ACC_PUBLIC, "close", "()V"),
+        );
+        let out = generate(TYPED_THROWS, &classes);
+
+        assert!(out.contains(&compact(
+            "pub fn read<'env>(self: &::java_spaghetti::Ref<'env, Self>,) \
+             -> ::std::result::Result<i32, FooReadError<'env>>"
+        )));
+        assert!(out.contains(&compact(
+            "pub enum FooReadError<'env> { \
+             IOException(::java_spaghetti::Local<'env, super::super::java::io::IOException>),"
+        )));
+        assert!(out.contains(&compact(
+            "impl<'env> ::std::convert::From<::java_spaghetti::Local<'env, super::super::java::lang::Throwable>> \
+             for FooReadError<'env>"
+        )));
+        assert!(out.contains(&compact(
+            "impl<'env> ::std::convert::From<FooReadError<'env>> \
+             for ::java_spaghetti::Local<'env, super::super::java::lang::Throwable>"
+        )));
+
+        // Methods without a `throws` clause keep the plain `Throwable` error.
+        assert!(out.contains(&compact(
+            "pub fn close<'env>(self: &::java_spaghetti::Ref<'env, Self>,) -> ::std::result::Result<(), \
+             ::java_spaghetti::Local<'env, super::super::java::lang::Throwable>>"
+        )));
+        assert!(!out.contains("FooCloseError"));
+    }
+
+    #[test]
+    fn test_declared_exceptions_enum_name_taken() {
+        let mut classes = throwables();
+        classes.push(
+            TestClass::class("com/example/Foo")
+                .method(ACC_PUBLIC, "read", "()I")
+                .throws("java/io/IOException"),
+        );
+        classes.push(TestClass::class("com/example/FooReadError"));
+        let out = generate(TYPED_THROWS, &classes);
+
+        assert!(!out.contains(&compact("enum FooReadError<'env>")));
+        assert!(out.contains(&compact(
+            "-> ::std::result::Result<i32, ::java_spaghetti::Local<'env, super::super::java::lang::Throwable>>"
+        )));
+    }
+
+    #[test]
+    fn test_declared_exceptions_enum_off_by_default() {
+        let mut classes = throwables();
+        classes.push(
+            TestClass::class("com/example/Foo")
+                .method(ACC_PUBLIC, "read", "()I")
+                .throws("java/io/IOException"),
+        );
+        let out = generate("", &classes);
+
+        assert!(!out.contains("FooReadError"));
+        assert!(out.contains(&compact(
+            "pub fn read<'env>(self: &::java_spaghetti::Ref<'env, Self>,) -> ::std::result::Result<i32, \
+             ::java_spaghetti::Local<'env, super::super::java::lang::Throwable>>"
+        )));
+    }
+
+    #[test]
+    fn test_declared_exceptions_enum_unwrap() {
+        // `Result::unwrap` needs a `Debug` error, and the preamble needs these methods for the one of `Throwable`.
+        let mut classes = throwables();
+        classes[1] = TestClass::class("java/lang/Throwable")
+            .method(ACC_PUBLIC, "getMessage", "()Ljava/lang/String;")
+            .method(ACC_PUBLIC, "getLocalizedMessage", "()Ljava/lang/String;")
+            .method(ACC_PUBLIC, "getStackTrace", "()[Ljava/lang/StackTraceElement;")
+            .method(ACC_PUBLIC, "toString", "()Ljava/lang/String;");
+        classes.push(TestClass::class("java/lang/String"));
+        classes.push(
+            TestClass::class("java/lang/StackTraceElement")
+                .method(ACC_PUBLIC, "getFileName", "()Ljava/lang/String;")
+                .method(ACC_PUBLIC, "getLineNumber", "()I")
+                .method(ACC_PUBLIC, "getClassName", "()Ljava/lang/String;")
+                .method(ACC_PUBLIC, "getMethodName", "()Ljava/lang/String;"),
+        );
+        classes.push(
+            TestClass::class("com/example/Foo")
+                .method(ACC_PUBLIC, "read", "()I")
+                .throws("java/io/IOException"),
+        );
+        let bindings = generate_source(TYPED_THROWS, &classes);
+
+        check(
+            "typed-throws-unwrap",
+            &bindings,
+            "use bindings::com::example::{Foo, FooReadError};\n\
+             \n\
+             #[allow(dead_code)]\n\
+             fn read(foo: &java_spaghetti::Ref<'_, Foo>) -> i32 {\n\
+             \x20   let _: Result<i32, FooReadError<'_>> = foo.read();\n\
+             \x20   foo.read().unwrap()\n\
+             }\n\
+             \n\
+             #[allow(dead_code)]\n\
+             fn describe(e: &FooReadError<'_>) -> String {\n\
+             \x20   e.to_string()\n\
+             }\n\
+             \n\
+             fn main() {}\n",
+        );
+    }
+
+    #[test]
+    fn test_declared_exceptions_enum_variant_docs() {
+        let mut classes = throwables();
+        classes.push(
+            TestClass::class("com/example/Foo")
+                .method(ACC_PUBLIC, "read", "()I")
+                .throws("java/io/IOException"),
+        );
+        let config = "rules:\n  - match: \"**\"\n    include: true\n    typed_throws: true\n    try_variants: true\n    \
+                      super_variants: true\n";
+        let out = generate(config, &classes);
+
+        for variant in ["pubfnsuper_read<", "pubfntry_read<"] {
+            let docs = &out[..out.find(variant).unwrap()];
+            let docs = &docs[docs.rfind("pubfn").unwrap()..];
+            assert!(docs.contains(&compact(
+                "Declared exceptions are returned as variants of [FooReadError]."
+            )));
+        }
+    }
 }
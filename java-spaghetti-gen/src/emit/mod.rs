@@ -4,12 +4,15 @@ mod class_proxy;
 mod classes;
 mod fields;
 pub mod java_proxy;
+mod java_proxy_bytecode;
 mod known_docs_url;
 mod methods;
 mod modules;
 mod preamble;
+#[cfg(test)]
+mod test_classes;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ffi::CString;
 use std::io;
 use std::rc::Rc;
@@ -23,25 +26,35 @@ use quote::{TokenStreamExt, format_ident, quote};
 use self::classes::Class;
 use self::modules::Module;
 use self::preamble::write_preamble;
+use crate::api_versions::{ApiLevels, ApiVersions};
 use crate::{config, parser_util, util};
 
 pub struct Context<'a> {
     pub(crate) config: &'a config::Config,
     pub(crate) module: Module,
     pub(crate) all_classes: HashMap<String, Rc<Class>>,
+    pub(crate) api_versions: Option<ApiVersions>,
     pub(crate) progress: Mutex<util::Progress>,
+    /// Items other than classes emitted so far, as `module::Name`, see [Context::claim_item_name].
+    pub(crate) item_names: Mutex<HashSet<String>>,
 }
 
 impl<'a> Context<'a> {
-    pub fn new(config: &'a config::Config) -> Self {
-        Self {
+    pub fn new(config: &'a config::Config) -> anyhow::Result<Self> {
+        let api_versions = match &config.api_versions {
+            Some(path) => Some(ApiVersions::read(path)?),
+            None => None,
+        };
+        Ok(Self {
             config,
             module: Default::default(),
             all_classes: HashMap::new(),
+            api_versions,
             progress: Mutex::new(util::Progress::with_duration(Duration::from_millis(
                 if config.logging_verbose { 0 } else { 300 },
             ))),
-        }
+            item_names: Mutex::new(HashSet::new()),
+        })
     }
 
     pub(crate) fn throwable_rust_path(&self, mod_: &str) -> TokenStream {
@@ -49,6 +62,30 @@ impl<'a> Context<'a> {
             .unwrap()
     }
 
+    /// Reserves `name` for an item emitted next to the classes of module `mod_`. Returns `false` if a class or
+    /// another item already has this name.
+    pub(crate) fn claim_item_name(&self, mod_: &str, name: &str) -> bool {
+        let module = mod_
+            .split("::")
+            .try_fold(&self.module, |module, fragment| module.modules.get(fragment));
+        let is_class = module.is_some_and(|module| module.classes.contains_key(name));
+        !is_class && self.item_names.lock().unwrap().insert(format!("{mod_}::{name}"))
+    }
+
+    /// Checks whether `class` is `base` or extends it, following the superclasses that are included in the bindings.
+    pub(crate) fn is_subclass_of(&self, class: parser_util::Id, base: parser_util::Id) -> bool {
+        let mut path = class.as_str();
+        loop {
+            if path == base.as_str() {
+                return true;
+            }
+            match self.all_classes.get(path).and_then(|c| c.java.super_path()) {
+                Some(super_path) => path = super_path.as_str(),
+                None => return false,
+            }
+        }
+    }
+
     pub fn java_to_rust_path(&self, java_class: parser_util::Id, mod_: &str) -> Result<TokenStream, anyhow::Error> {
         let m = Class::mod_for(java_class)?;
         let s = Class::name_for(java_class)?;
@@ -89,6 +126,10 @@ impl<'a> Context<'a> {
         if !cc.include {
             return Ok(());
         }
+        let levels = self.api_versions.as_ref().and_then(|a| a.class(class.path().as_str()));
+        if !is_available(levels, cc.min_api_level) {
+            return Ok(());
+        }
 
         let java_path = class.path().as_str().to_string();
         let s = Rc::new(Class::new(class)?);
@@ -100,7 +141,7 @@ impl<'a> Context<'a> {
             rust_mod = rust_mod.modules.entry(fragment.to_owned()).or_default();
         }
         if rust_mod.classes.contains_key(&s.rust.struct_name) {
-            return io_data_err!(
+            io_data_err!(
                 "Unable to add_class(): java class name {:?} was already added",
                 &s.rust.struct_name
             )?;
@@ -111,11 +152,30 @@ impl<'a> Context<'a> {
     }
 
     pub fn write(&self, out: &mut impl io::Write) -> anyhow::Result<()> {
-        write_preamble(out)?;
+        write_preamble(self, out)?;
         self.module.write(self, out)
     }
 }
 
+/// Checks whether something with the given API levels can be used on all devices running `min_api_level` or newer.
+fn is_available(levels: Option<ApiLevels>, min_api_level: Option<u32>) -> bool {
+    match (levels.and_then(|l| l.since), min_api_level) {
+        (Some(since), Some(min_api_level)) => since <= min_api_level,
+        _ => true,
+    }
+}
+
+/// Documents the API levels in which something was added, deprecated and removed, as an extra paragraph.
+fn api_levels_docs(levels: Option<ApiLevels>) -> TokenStream {
+    match levels {
+        Some(levels) if levels != ApiLevels::default() => {
+            let docs = format!("\n\n{levels}");
+            quote!(#[doc = #docs])
+        }
+        _ => quote!(),
+    }
+}
+
 fn cstring(s: &str) -> Literal {
     Literal::c_string(&CString::from_str(s).unwrap())
 }
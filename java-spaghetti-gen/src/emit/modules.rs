@@ -26,7 +26,7 @@ impl Module {
             writeln!(out, "}}")?;
         }
 
-        for (_, class) in self.classes.iter() {
+        for class in self.classes.values() {
             let res = class.write(context)?;
             out.write_all(dumb_format(res).as_bytes())?;
         }
@@ -12,7 +12,7 @@ mod util {
     use std::fmt;
 
     use java_spaghetti::sys::jsize;
-    use java_spaghetti::{Env, JavaDebug, Local, Ref, StringChars, ThrowableType};
+    use java_spaghetti::{Env, JavaDebug, Local, Ref, StringChars, StringUtfChars};
 
     use super::java::lang::{String as JString, Throwable};
 
@@ -79,9 +79,7 @@ mod util {
     impl JString {
         /// Create new local string from an Env + AsRef<str>
         pub fn from_env_str<'env, S: AsRef<str>>(env: Env<'env>, string: S) -> Local<'env, Self> {
-            let chars = string.as_ref().encode_utf16().collect::<Vec<_>>();
-
-            let string = unsafe { env.new_string(chars.as_ptr(), chars.len() as jsize) };
+            let string = env.new_string_from_str(string.as_ref());
             unsafe { Local::from_raw(env, string) }
         }
 
@@ -89,6 +87,52 @@ mod util {
             unsafe { StringChars::from_env_jstring(self.env(), self.as_raw()) }
         }
 
+        /// Returns the string as JNI Modified UTF-8, see [java_spaghetti::from_modified_utf8].
+        pub fn string_utf_chars<'env>(self: &Ref<'env, Self>) -> StringUtfChars<'env> {
+            unsafe { StringUtfChars::from_env_jstring(self.env(), self.as_raw()) }
+        }
+
+        /// Copies the UTF-16 units `start..start + buf.len()` into `buf`, or returns the
+        /// `StringIndexOutOfBoundsException` if the range is out of bounds.
+        pub fn get_region<'env>(
+            self: &Ref<'env, Self>,
+            start: usize,
+            buf: &mut [u16],
+        ) -> Result<(), Local<'env, Throwable>> {
+            assert!(start <= jsize::MAX as usize && buf.len() <= jsize::MAX as usize);
+            let env = self.env();
+            unsafe { env.get_string_region(self.as_raw(), start as jsize, buf.len() as jsize, buf.as_mut_ptr()) };
+            env.exception_check()
+        }
+
+        /// Appends the Modified UTF-8 encoding of the UTF-16 units `start..start + len` to `buf`, or returns the
+        /// `StringIndexOutOfBoundsException` if the range is out of bounds.
+        pub fn get_utf_region<'env>(
+            self: &Ref<'env, Self>,
+            start: usize,
+            len: usize,
+            buf: &mut Vec<u8>,
+        ) -> Result<(), Local<'env, Throwable>> {
+            assert!(start <= jsize::MAX as usize && len <= jsize::MAX as usize);
+            let env = self.env();
+            let old_len = buf.len();
+            // `GetStringUTFRegion` doesn't return the written length. Each UTF-16 unit takes up to 3 bytes in Modified
+            // UTF-8, plus 1 for the NUL terminator some VMs add, and Modified UTF-8 has no zero bytes: the written
+            // length is where the zero-filled space starts. Nothing is written if the range is out of bounds.
+            buf.resize(old_len + 3 * len + 1, 0);
+            unsafe {
+                env.get_string_utf_region(
+                    self.as_raw(),
+                    start as jsize,
+                    len as jsize,
+                    buf[old_len..].as_mut_ptr() as *mut _,
+                )
+            };
+            let written = buf[old_len..].iter().position(|b| *b == 0).unwrap();
+            buf.truncate(old_len + written);
+            env.exception_check()
+        }
+
         /// Returns a new [Ok]\([String]\), or an [Err]\([DecodeUtf16Error]\) if if it contained any invalid UTF16.
         ///
         /// [Ok]:                       https://doc.rust-lang.org/std/result/enum.Result.html#variant.Ok
@@ -97,6 +141,9 @@ mod util {
         /// [String]:                   https://doc.rust-lang.org/std/string/struct.String.html
         /// [REPLACEMENT_CHARACTER]:    https://doc.rust-lang.org/std/char/constant.REPLACEMENT_CHARACTER.html
         pub fn to_string(self: &Ref<'_, Self>) -> Result<String, DecodeUtf16Error> {
+            if let Some(string) = unsafe { self.env().get_string_ascii(self.as_raw()) } {
+                return Ok(string);
+            }
             self.string_chars().to_string()
         }
 
@@ -105,6 +152,9 @@ mod util {
         /// [String]:                   https://doc.rust-lang.org/std/string/struct.String.html
         /// [REPLACEMENT_CHARACTER]:    https://doc.rust-lang.org/std/char/constant.REPLACEMENT_CHARACTER.html
         pub fn to_string_lossy(self: &Ref<'_, Self>) -> String {
+            if let Some(string) = unsafe { self.env().get_string_ascii(self.as_raw()) } {
+                return string;
+            }
             self.string_chars().to_string_lossy()
         }
     }
@@ -115,6 +165,4 @@ mod util {
             fmt::Debug::fmt(&self.to_string_lossy(), f) // XXX: Unneccessary alloc?  Shouldn't use lossy here?
         }
     }
-
-    impl ThrowableType for Throwable {}
 }
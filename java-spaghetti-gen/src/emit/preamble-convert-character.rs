@@ -0,0 +1,23 @@
+    impl<'env> IntoJava<'env> for char {
+        type Java = super::java::lang::Character;
+
+        /// Panics for supplementary characters, which don't fit into a `Character`.
+        fn into_java(self, env: Env<'env>) -> Result<Option<Local<'env, Self::Java>>, IntoJavaError<'env>> {
+            let [c] = *self.encode_utf16(&mut [0; 2]) else {
+                panic!("{self:?} is not representable as java.lang.Character");
+            };
+            let descriptor = c"(C)Ljava/lang/Character;";
+            let boxed = call_static::<Self::Java>(env, c"valueOf", descriptor, &[JValue::Char(c)])??;
+            Ok(unsafe { downcast(boxed) })
+        }
+    }
+
+    impl<'env> FromJava<'env> for char {
+        type Java = super::java::lang::Character;
+
+        /// Unpaired surrogates are replaced with `'\u{FFFD}'`.
+        fn from_java(object: Option<&Ref<'env, Self::Java>>) -> Result<Self, FromJavaError<'env>> {
+            let value = call(object.ok_or(FromJavaError::Null)?, c"charValue", c"()C", &[])??;
+            Ok(char::from_u32(value.as_char().unwrap().into()).unwrap_or(char::REPLACEMENT_CHARACTER))
+        }
+    }
@@ -0,0 +1,15 @@
+    impl<'env, T: IntoJava<'env>> IntoJava<'env> for Vec<T> {
+        type Java = super::java::util::ArrayList;
+
+        fn into_java(self, env: Env<'env>) -> Result<Option<Local<'env, Self::Java>>, IntoJavaError<'env>> {
+            new_collection(env, self)
+        }
+    }
+
+    impl<'env, T: FromJava<'env>> FromJava<'env> for Vec<T> {
+        type Java = super::java::util::List;
+
+        fn from_java(object: Option<&Ref<'env, Self::Java>>) -> Result<Self, FromJavaError<'env>> {
+            collection_elements(object.ok_or(FromJavaError::Null)?)
+        }
+    }
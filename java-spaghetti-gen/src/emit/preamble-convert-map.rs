@@ -0,0 +1,67 @@
+    fn new_map<'env, K: IntoJava<'env>, V: IntoJava<'env>>(
+        env: Env<'env>,
+        entries: impl IntoIterator<Item = (K, V)>,
+    ) -> Result<Option<Local<'env, super::java::util::HashMap>>, IntoJavaError<'env>> {
+        let map = new_object::<super::java::util::HashMap>(env)??;
+        let descriptor = c"(Ljava/lang/Object;Ljava/lang/Object;)Ljava/lang/Object;";
+        for (key, value) in entries {
+            let (key, value) = (key.into_java(env)?, value.into_java(env)?);
+            call(&map, c"put", descriptor, &[key.into(), value.into()])??;
+        }
+        Ok(Some(map))
+    }
+
+    /// Converts the entries of a `java.util.Map`, read with `entrySet().toArray()`.
+    fn map_entries<'env, K: FromJava<'env>, V: FromJava<'env>, C: FromIterator<(K, V)>>(
+        map: &Ref<'env, super::java::util::Map>,
+    ) -> Result<C, FromJavaError<'env>> {
+        let env = map.env();
+        let entries = call(map, c"entrySet", c"()Ljava/util/Set;", &[])??;
+        let entries = entries.into_object().ok_or(FromJavaError::Null)?;
+        let entries = collection_array(&entries)?;
+        // Converted keys and values may keep a local reference each, e.g. `HashMap<String, Local<T>>`.
+        env.ensure_local_capacity::<Throwable>(2 * entries.len())?;
+        let get = |entry: &Ref<'env, JObject>, name: &CStr| {
+            env.call_method::<Throwable>(entry, c"java/util/Map$Entry", name, c"()Ljava/lang/Object;", &[])
+        };
+        (0..entries.len())
+            .map(|index| {
+                let entry = entries.get(index)?.ok_or(FromJavaError::Null)?;
+                let key = element(get(&entry, c"getKey")??.into_object())?;
+                let value = element(get(&entry, c"getValue")??.into_object())?;
+                Ok((key, value))
+            })
+            .collect()
+    }
+
+    impl<'env, K: IntoJava<'env>, V: IntoJava<'env>> IntoJava<'env> for HashMap<K, V> {
+        type Java = super::java::util::HashMap;
+
+        fn into_java(self, env: Env<'env>) -> Result<Option<Local<'env, Self::Java>>, IntoJavaError<'env>> {
+            new_map(env, self)
+        }
+    }
+
+    impl<'env, K: IntoJava<'env>, V: IntoJava<'env>> IntoJava<'env> for BTreeMap<K, V> {
+        type Java = super::java::util::HashMap;
+
+        fn into_java(self, env: Env<'env>) -> Result<Option<Local<'env, Self::Java>>, IntoJavaError<'env>> {
+            new_map(env, self)
+        }
+    }
+
+    impl<'env, K: FromJava<'env> + Eq + Hash, V: FromJava<'env>> FromJava<'env> for HashMap<K, V> {
+        type Java = super::java::util::Map;
+
+        fn from_java(object: Option<&Ref<'env, Self::Java>>) -> Result<Self, FromJavaError<'env>> {
+            map_entries(object.ok_or(FromJavaError::Null)?)
+        }
+    }
+
+    impl<'env, K: FromJava<'env> + Ord, V: FromJava<'env>> FromJava<'env> for BTreeMap<K, V> {
+        type Java = super::java::util::Map;
+
+        fn from_java(object: Option<&Ref<'env, Self::Java>>) -> Result<Self, FromJavaError<'env>> {
+            map_entries(object.ok_or(FromJavaError::Null)?)
+        }
+    }
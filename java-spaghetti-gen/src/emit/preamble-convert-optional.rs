@@ -0,0 +1,19 @@
+    impl super::java::util::Optional {
+        /// Converts `value` into an `Optional`, which is empty for `None` and for values converted to `null`.
+        pub fn from_option<'env, T: IntoJava<'env>>(
+            env: Env<'env>,
+            value: Option<T>,
+        ) -> Result<Local<'env, Self>, IntoJavaError<'env>> {
+            let value = value.into_java(env)?;
+            let descriptor = c"(Ljava/lang/Object;)Ljava/util/Optional;";
+            let optional = call_static::<Self>(env, c"ofNullable", descriptor, &[value.into()])??;
+            unsafe { downcast(optional) }.ok_or(IntoJavaError::Null)
+        }
+
+        /// Converts the value of this `Optional`, if it is present.
+        pub fn to_option<'env, T: FromJava<'env>>(self: &Ref<'env, Self>) -> Result<Option<T>, FromJavaError<'env>> {
+            let descriptor = c"(Ljava/lang/Object;)Ljava/lang/Object;";
+            let value = call(self, c"orElse", descriptor, &[JValue::Object(None)])??;
+            element(value.into_object())
+        }
+    }
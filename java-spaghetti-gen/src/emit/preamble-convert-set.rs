@@ -0,0 +1,31 @@
+    impl<'env, T: IntoJava<'env>> IntoJava<'env> for HashSet<T> {
+        type Java = super::java::util::HashSet;
+
+        fn into_java(self, env: Env<'env>) -> Result<Option<Local<'env, Self::Java>>, IntoJavaError<'env>> {
+            new_collection(env, self)
+        }
+    }
+
+    impl<'env, T: IntoJava<'env>> IntoJava<'env> for BTreeSet<T> {
+        type Java = super::java::util::HashSet;
+
+        fn into_java(self, env: Env<'env>) -> Result<Option<Local<'env, Self::Java>>, IntoJavaError<'env>> {
+            new_collection(env, self)
+        }
+    }
+
+    impl<'env, T: FromJava<'env> + Eq + Hash> FromJava<'env> for HashSet<T> {
+        type Java = super::java::util::Set;
+
+        fn from_java(object: Option<&Ref<'env, Self::Java>>) -> Result<Self, FromJavaError<'env>> {
+            collection_elements(object.ok_or(FromJavaError::Null)?)
+        }
+    }
+
+    impl<'env, T: FromJava<'env> + Ord> FromJava<'env> for BTreeSet<T> {
+        type Java = super::java::util::Set;
+
+        fn from_java(object: Option<&Ref<'env, Self::Java>>) -> Result<Self, FromJavaError<'env>> {
+            collection_elements(object.ok_or(FromJavaError::Null)?)
+        }
+    }
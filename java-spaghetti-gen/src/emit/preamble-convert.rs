@@ -0,0 +1,289 @@
+    //! Conversions between Rust standard types and the Java classes included in the bindings, with [IntoJava] and
+    //! [FromJava]. Conversions are emitted for the included classes among:
+    //!
+    //! | Rust                                | Java                                                       |
+    //! |-------------------------------------|------------------------------------------------------------|
+    //! | `bool`, `i8`, `i16`, `i32`, `i64`   | `Boolean`, `Byte`, `Short`, `Integer`, `Long`              |
+    //! | `f32`, `f64`, `char`                | `Float`, `Double`, `Character`                             |
+    //! | `String`, `&str`                    | `String`                                                   |
+    //! | `Option<T>`                         | `T` or `null`                                              |
+    //! | `Box<[T]>`, `[T; N]`                | `T[]`                                                      |
+    //! | `Vec<T>`                            | `java.util.ArrayList`, from any `java.util.List`           |
+    //! | `HashSet<T>`, `BTreeSet<T>`         | `java.util.HashSet`, from any `java.util.Set`              |
+    //! | `HashMap<K, V>`, `BTreeMap<K, V>`   | `java.util.HashMap`, from any `java.util.Map`              |
+    //! | `Local<T>`                          | `T`                                                        |
+    //!
+    //! `java.util.Optional` is converted from and to `Option` with `Optional::from_option` and `Optional::to_option`.
+    //! Primitive arrays are converted by [java_spaghetti::PrimitiveArray].
+
+    use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+    use std::ffi::CStr;
+    use std::hash::Hash;
+
+    use java_spaghetti::{Env, JObject, JValue, JniType, Local, LookupError, ObjectArray, Ref, ReferenceType};
+
+    use super::java::lang::{String as JString, Throwable};
+
+    /// Converts a Rust value into a Java object, or `null`.
+    pub trait IntoJava<'env> {
+        /// The class of the converted object.
+        type Java: ReferenceType;
+
+        fn into_java(self, env: Env<'env>) -> Result<Option<Local<'env, Self::Java>>, IntoJavaError<'env>>;
+    }
+
+    /// Converts a Java object, or `null`, into a Rust value.
+    pub trait FromJava<'env>: Sized {
+        /// The class of the converted object.
+        type Java: ReferenceType;
+
+        fn from_java(object: Option<&Ref<'env, Self::Java>>) -> Result<Self, FromJavaError<'env>>;
+    }
+
+    /// Error returned by [IntoJava].
+    #[derive(Debug)]
+    pub enum IntoJavaError<'env> {
+        /// A Java method threw an exception.
+        Exception(Local<'env, Throwable>),
+        /// A Java method returned `null` where an object is expected.
+        Null,
+        /// A class or method used by the conversion is not found.
+        Lookup(LookupError),
+    }
+
+    impl<'env> From<Local<'env, Throwable>> for IntoJavaError<'env> {
+        fn from(exception: Local<'env, Throwable>) -> Self {
+            Self::Exception(exception)
+        }
+    }
+
+    impl<'env> From<LookupError> for IntoJavaError<'env> {
+        fn from(error: LookupError) -> Self {
+            Self::Lookup(error)
+        }
+    }
+
+    /// Error returned by [FromJava].
+    #[derive(Debug)]
+    pub enum FromJavaError<'env> {
+        /// A Java method threw an exception.
+        Exception(Local<'env, Throwable>),
+        /// An object is `null` where a value is expected.
+        Null,
+        /// An element of an array or collection is not an instance of the expected class.
+        Cast,
+        /// A class or method used by the conversion is not found.
+        Lookup(LookupError),
+    }
+
+    impl<'env> From<Local<'env, Throwable>> for FromJavaError<'env> {
+        fn from(exception: Local<'env, Throwable>) -> Self {
+            Self::Exception(exception)
+        }
+    }
+
+    impl<'env> From<LookupError> for FromJavaError<'env> {
+        fn from(error: LookupError) -> Self {
+            Self::Lookup(error)
+        }
+    }
+
+    /// Converts an object returned by a dynamic call that is known to be a `T`.
+    unsafe fn downcast<'env, T: ReferenceType>(value: JValue<'env>) -> Option<Local<'env, T>> {
+        value
+            .into_object()
+            .map(|object| unsafe { Local::from_raw(object.env(), object.into_raw()) })
+    }
+
+    /// Calls a method of `T` by name and JNI descriptor.
+    fn call<'env, T: ReferenceType>(
+        this: &Ref<'env, T>,
+        name: &CStr,
+        descriptor: &CStr,
+        args: &[JValue<'env>],
+    ) -> Result<Result<JValue<'env>, Local<'env, Throwable>>, LookupError> {
+        this.call_method(name, descriptor, args)
+    }
+
+    /// Calls a static method of `T` by name and JNI descriptor.
+    fn call_static<'env, T: ReferenceType>(
+        env: Env<'env>,
+        name: &CStr,
+        descriptor: &CStr,
+        args: &[JValue<'env>],
+    ) -> Result<Result<JValue<'env>, Local<'env, Throwable>>, LookupError> {
+        T::static_with_jni_type(|class| env.call_static_method(class, name, descriptor, args))
+    }
+
+    /// Creates an object of class `C` with its no-argument constructor.
+    fn new_object<'env, C: ReferenceType>(
+        env: Env<'env>,
+    ) -> Result<Result<Local<'env, C>, Local<'env, Throwable>>, LookupError> {
+        let object = C::static_with_jni_type(|class| env.new_object(class, c"()V", &[]))?;
+        Ok(object.map(|object| unsafe { Local::from_raw(env, object.into_raw()) }))
+    }
+
+    /// Casts an element of an array or collection to the class of `T`, and converts it.
+    fn element<'env, T: FromJava<'env>>(object: Option<Local<'env, JObject>>) -> Result<T, FromJavaError<'env>> {
+        let object = match object {
+            Some(object) => Some(object.cast::<T::Java>().map_err(|_| FromJavaError::Cast)?),
+            None => None,
+        };
+        T::from_java(object.as_deref())
+    }
+
+    /// Creates a collection of class `C` with its no-argument constructor, and adds the converted `elements` to it.
+    fn new_collection<'env, C: ReferenceType, T: IntoJava<'env>>(
+        env: Env<'env>,
+        elements: impl IntoIterator<Item = T>,
+    ) -> Result<Option<Local<'env, C>>, IntoJavaError<'env>> {
+        let collection = new_object::<C>(env)??;
+        // The converted elements are deleted once added, so the loop doesn't accumulate local references.
+        for element in elements {
+            let element = element.into_java(env)?;
+            call(&collection, c"add", c"(Ljava/lang/Object;)Z", &[element.into()])??;
+        }
+        Ok(Some(collection))
+    }
+
+    /// Reads the elements of a `java.util.Collection` with `toArray()`.
+    fn collection_array<'env>(
+        collection: &Ref<'env, impl ReferenceType>,
+    ) -> Result<Local<'env, ObjectArray<JObject, Throwable>>, FromJavaError<'env>> {
+        let env = collection.env();
+        let descriptor = c"()[Ljava/lang/Object;";
+        let array = env.call_method(collection, c"java/util/Collection", c"toArray", descriptor, &[])??;
+        unsafe { downcast(array) }.ok_or(FromJavaError::Null)
+    }
+
+    /// Converts the elements of a `java.util.Collection`, read with `toArray()`.
+    fn collection_elements<'env, T: FromJava<'env>, C: FromIterator<T>>(
+        collection: &Ref<'env, impl ReferenceType>,
+    ) -> Result<C, FromJavaError<'env>> {
+        let array = collection_array(collection)?;
+        // Converted elements may keep a local reference each, e.g. `Vec<Local<T>>`.
+        array.env().ensure_local_capacity::<Throwable>(array.len())?;
+        (0..array.len()).map(|index| element(array.get(index)?)).collect()
+    }
+
+    impl<'env> IntoJava<'env> for &str {
+        type Java = JString;
+
+        fn into_java(self, env: Env<'env>) -> Result<Option<Local<'env, JString>>, IntoJavaError<'env>> {
+            Ok(Some(JString::from_env_str(env, self)))
+        }
+    }
+
+    impl<'env> IntoJava<'env> for String {
+        type Java = JString;
+
+        fn into_java(self, env: Env<'env>) -> Result<Option<Local<'env, JString>>, IntoJavaError<'env>> {
+            self.as_str().into_java(env)
+        }
+    }
+
+    impl<'env> FromJava<'env> for String {
+        type Java = JString;
+
+        /// Unpaired surrogates are replaced with `'\u{FFFD}'`.
+        fn from_java(object: Option<&Ref<'env, JString>>) -> Result<Self, FromJavaError<'env>> {
+            Ok(object.ok_or(FromJavaError::Null)?.to_string_lossy())
+        }
+    }
+
+    impl<'env, T: IntoJava<'env>> IntoJava<'env> for Option<T> {
+        type Java = T::Java;
+
+        fn into_java(self, env: Env<'env>) -> Result<Option<Local<'env, T::Java>>, IntoJavaError<'env>> {
+            match self {
+                Some(value) => value.into_java(env),
+                None => Ok(None),
+            }
+        }
+    }
+
+    impl<'env, T: FromJava<'env>> FromJava<'env> for Option<T> {
+        type Java = T::Java;
+
+        fn from_java(object: Option<&Ref<'env, T::Java>>) -> Result<Self, FromJavaError<'env>> {
+            object.map(|object| T::from_java(Some(object))).transpose()
+        }
+    }
+
+    impl<'env, T: ReferenceType> IntoJava<'env> for Local<'env, T> {
+        type Java = T;
+
+        fn into_java(self, _env: Env<'env>) -> Result<Option<Local<'env, T>>, IntoJavaError<'env>> {
+            Ok(Some(self))
+        }
+    }
+
+    impl<'env, T: ReferenceType> FromJava<'env> for Local<'env, T> {
+        type Java = T;
+
+        fn from_java(object: Option<&Ref<'env, T>>) -> Result<Self, FromJavaError<'env>> {
+            Ok(object.ok_or(FromJavaError::Null)?.as_local())
+        }
+    }
+
+    fn new_array<'env, T: IntoJava<'env>>(
+        env: Env<'env>,
+        elements: impl ExactSizeIterator<Item = T>,
+    ) -> Result<Option<Local<'env, ObjectArray<T::Java, Throwable>>>, IntoJavaError<'env>> {
+        let array = ObjectArray::<T::Java, Throwable>::new(env, elements.len());
+        for (index, element) in elements.enumerate() {
+            array.set(index, element.into_java(env)?)?;
+        }
+        Ok(Some(array))
+    }
+
+    impl<'env, T: IntoJava<'env>> IntoJava<'env> for Box<[T]> {
+        type Java = ObjectArray<T::Java, Throwable>;
+
+        fn into_java(self, env: Env<'env>) -> Result<Option<Local<'env, Self::Java>>, IntoJavaError<'env>> {
+            new_array(env, self.into_vec().into_iter())
+        }
+    }
+
+    impl<'env, T: IntoJava<'env>, const N: usize> IntoJava<'env> for [T; N] {
+        type Java = ObjectArray<T::Java, Throwable>;
+
+        fn into_java(self, env: Env<'env>) -> Result<Option<Local<'env, Self::Java>>, IntoJavaError<'env>> {
+            new_array(env, self.into_iter())
+        }
+    }
+
+    impl<'env, T: FromJava<'env>> FromJava<'env> for Box<[T]> {
+        type Java = ObjectArray<T::Java, Throwable>;
+
+        fn from_java(object: Option<&Ref<'env, Self::Java>>) -> Result<Self, FromJavaError<'env>> {
+            let array = object.ok_or(FromJavaError::Null)?;
+            array.env().ensure_local_capacity::<Throwable>(array.len())?;
+            (0..array.len())
+                .map(|index| T::from_java(array.get(index)?.as_deref()))
+                .collect()
+        }
+    }
+
+    /// Implements the conversions of a primitive type and its boxed class in `java.lang`.
+    macro_rules! boxed {
+        ($rust:ty, $class:ident, $value_of:literal, $unbox:literal, $unbox_descriptor:literal, $as:ident) => {
+            impl<'env> IntoJava<'env> for $rust {
+                type Java = super::java::lang::$class;
+
+                fn into_java(self, env: Env<'env>) -> Result<Option<Local<'env, Self::Java>>, IntoJavaError<'env>> {
+                    let boxed = call_static::<Self::Java>(env, c"valueOf", $value_of, &[self.into()])??;
+                    Ok(unsafe { downcast(boxed) })
+                }
+            }
+
+            impl<'env> FromJava<'env> for $rust {
+                type Java = super::java::lang::$class;
+
+                fn from_java(object: Option<&Ref<'env, Self::Java>>) -> Result<Self, FromJavaError<'env>> {
+                    let value = call(object.ok_or(FromJavaError::Null)?, $unbox, $unbox_descriptor, &[])??;
+                    Ok(value.$as().unwrap())
+                }
+            }
+        };
+    }
@@ -0,0 +1,108 @@
+/// Rust futures over `java.util.concurrent.CompletableFuture`s, returned by their `to_future()`.
+///
+/// The futures don't depend on any async runtime: a `java.util.function.BiConsumer` proxy registered with
+/// `whenComplete()` stores the outcome and wakes the last waker, from whichever Java thread completes the future.
+pub mod future {
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::{Arc, Mutex};
+    use std::task::{Context, Poll, Waker};
+
+    use java_spaghetti::{Env, Global, Local, Ref, ReferenceType};
+
+    use super::java::lang::{Object, Throwable};
+    use super::java::util::concurrent::CompletableFuture;
+    use super::java::util::function::BiConsumer;
+
+    /// The outcome of a `CompletableFuture`: its value, which may be `null`, or the exception it completed with.
+    pub type Outcome<T> = Result<Option<Global<T>>, Global<Throwable>>;
+
+    /// A [Future] resolving to the [Outcome] of a `CompletableFuture`.
+    ///
+    /// Values that are not instances of `T` resolve to a `ClassCastException`. Exceptions are passed on as
+    /// `whenComplete()` receives them, so the failures of dependent stages are wrapped in a `CompletionException`.
+    pub struct JavaFuture<T: ReferenceType> {
+        /// `None` once the outcome has been returned.
+        state: Option<Arc<Mutex<State<T>>>>,
+    }
+
+    struct State<T: ReferenceType> {
+        outcome: Option<Outcome<T>>,
+        waker: Option<Waker>,
+    }
+
+    impl<T: ReferenceType> JavaFuture<T> {
+        /// Registers the completion callback on `future`. It runs right away if `future` is already complete.
+        pub fn new<'env>(future: &Ref<'env, CompletableFuture>) -> Result<Self, Local<'env, Throwable>> {
+            let state = Arc::new(Mutex::new(State {
+                outcome: None,
+                waker: None,
+            }));
+            let callback = BiConsumer::from_fn(future.env(), {
+                let state = state.clone();
+                move |env, value, exception| complete(&state, env, value, exception)
+            })?;
+            future.whenComplete(&callback)?;
+            Ok(Self { state: Some(state) })
+        }
+    }
+
+    impl<T: ReferenceType> Future for JavaFuture<T> {
+        type Output = Outcome<T>;
+
+        /// # Panics
+        ///
+        /// If polled again after returning [Poll::Ready].
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+            let mut state = self.state.as_ref().expect("JavaFuture polled after completion").lock().unwrap();
+            if let Some(outcome) = state.outcome.take() {
+                drop(state);
+                self.state = None;
+                return Poll::Ready(outcome);
+            }
+            match &mut state.waker {
+                Some(waker) => waker.clone_from(cx.waker()),
+                None => state.waker = Some(cx.waker().clone()),
+            }
+            Poll::Pending
+        }
+    }
+
+    impl CompletableFuture {
+        /// Returns a [Future] resolving to the value or exception this `CompletableFuture` completes with.
+        pub fn to_future<'env, T: ReferenceType>(
+            self: &Ref<'env, Self>,
+        ) -> Result<JavaFuture<T>, Local<'env, Throwable>> {
+            JavaFuture::new(self)
+        }
+    }
+
+    /// The `whenComplete()` callback: stores the outcome and wakes the last waker.
+    fn complete<'env, T: ReferenceType>(
+        state: &Mutex<State<T>>,
+        env: Env<'env>,
+        value: Option<Ref<'env, Object>>,
+        exception: Option<Ref<'env, Object>>,
+    ) {
+        let outcome = match (value, exception) {
+            (_, Some(exception)) => Err(exception.cast::<Throwable>().unwrap().as_global()),
+            (None, None) => Ok(None),
+            (Some(value), None) => match value.as_local().downcast::<T>() {
+                Ok(value) => Ok(Some(value.as_global())),
+                Err(_) => {
+                    let class = T::static_with_jni_type(|class| class.to_string_lossy().replace('/', "."));
+                    env.throw_new(c"java/lang/ClassCastException", &format!("value is not an instance of {class}"));
+                    Err(env.exception_take::<Throwable>().unwrap().as_global())
+                }
+            },
+        };
+        let waker = {
+            let mut state = state.lock().unwrap();
+            state.outcome = Some(outcome);
+            state.waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
@@ -0,0 +1,67 @@
+/// Rust iterators over the `java.lang.Iterable`s of the bindings, returned by their `iter()` and `iter_as()`.
+pub mod iter {
+    use std::marker::PhantomData;
+
+    use java_spaghetti::{JniType, Local, Ref, ReferenceType};
+
+    use super::java::lang::{Iterable, Object, Throwable};
+    use super::java::util::Iterator as JIterator;
+
+    /// An iterator over the elements of a `java.lang.Iterable`, with the `hasNext()` and `next()` of its
+    /// `iterator()`. Elements that are not instances of `T` are returned as a `ClassCastException`.
+    ///
+    /// Each element is read in its own local reference frame, so that only the returned elements hold local
+    /// references. The iteration stops after the first exception.
+    pub struct Iter<'env, T: ReferenceType> {
+        /// `None` once the iteration is over.
+        iterator: Option<Result<Local<'env, JIterator>, Local<'env, Throwable>>>,
+        _element: PhantomData<T>,
+    }
+
+    impl<'env, T: ReferenceType> Iter<'env, T> {
+        pub fn new(iterable: &Ref<'env, Iterable>) -> Self {
+            Self {
+                iterator: iterable.iterator().transpose(),
+                _element: PhantomData,
+            }
+        }
+    }
+
+    impl<'env, T: ReferenceType> Iterator for Iter<'env, T> {
+        type Item = Result<Option<Local<'env, T>>, Local<'env, Throwable>>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            let iterator = match self.iterator.take()? {
+                Ok(iterator) => iterator,
+                Err(exception) => return Some(Err(exception)),
+            };
+            let item = match iterator.hasNext() {
+                Ok(false) => return None,
+                // Safety: the outer `iterator` is only used to create a `Ref` with the `Env` of the frame.
+                Ok(true) => unsafe {
+                    iterator.env().with_local_frame_returning(4, |env| {
+                        let iterator = Ref::<JIterator>::from_raw(env, iterator.as_raw());
+                        match iterator.next()? {
+                            Some(element) => cast(element).map(Some),
+                            None => Ok(None),
+                        }
+                    })
+                },
+                Err(exception) => Err(exception),
+            };
+            if item.is_ok() {
+                self.iterator = Some(Ok(iterator));
+            }
+            Some(item)
+        }
+    }
+
+    fn cast<'env, T: ReferenceType>(element: Local<'env, Object>) -> Result<Local<'env, T>, Local<'env, Throwable>> {
+        element.downcast().map_err(|element| {
+            let env = element.env();
+            let class = T::static_with_jni_type(|class| class.to_string_lossy().replace('/', "."));
+            env.throw_new(c"java/lang/ClassCastException", &format!("element is not an instance of {class}"));
+            env.exception_take().unwrap()
+        })
+    }
+}
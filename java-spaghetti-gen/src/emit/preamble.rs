@@ -1,8 +1,152 @@
 use std::io::{self, Write};
 
-pub fn write_preamble(out: &mut impl Write) -> io::Result<()> {
+use crate::emit::Context;
+
+/// Conversions of primitive types, emitted if their boxed class is included.
+const BOXED: &[(&str, &str)] = &[
+    (
+        "java/lang/Boolean",
+        r#"boxed!(bool, Boolean, c"(Z)Ljava/lang/Boolean;", c"booleanValue", c"()Z", as_bool);"#,
+    ),
+    (
+        "java/lang/Byte",
+        r#"boxed!(i8, Byte, c"(B)Ljava/lang/Byte;", c"byteValue", c"()B", as_byte);"#,
+    ),
+    (
+        "java/lang/Short",
+        r#"boxed!(i16, Short, c"(S)Ljava/lang/Short;", c"shortValue", c"()S", as_short);"#,
+    ),
+    (
+        "java/lang/Integer",
+        r#"boxed!(i32, Integer, c"(I)Ljava/lang/Integer;", c"intValue", c"()I", as_int);"#,
+    ),
+    (
+        "java/lang/Long",
+        r#"boxed!(i64, Long, c"(J)Ljava/lang/Long;", c"longValue", c"()J", as_long);"#,
+    ),
+    (
+        "java/lang/Float",
+        r#"boxed!(f32, Float, c"(F)Ljava/lang/Float;", c"floatValue", c"()F", as_float);"#,
+    ),
+    (
+        "java/lang/Double",
+        r#"boxed!(f64, Double, c"(D)Ljava/lang/Double;", c"doubleValue", c"()D", as_double);"#,
+    ),
+];
+
+/// Other conversions, emitted if all of their classes are included.
+const CONVERSIONS: &[(&[&str], &str)] = &[
+    (&["java/lang/Character"], include_str!("preamble-convert-character.rs")),
+    (
+        &["java/util/List", "java/util/ArrayList"],
+        include_str!("preamble-convert-list.rs"),
+    ),
+    (
+        &["java/util/Set", "java/util/HashSet"],
+        include_str!("preamble-convert-set.rs"),
+    ),
+    (
+        &["java/util/Map", "java/util/HashMap"],
+        include_str!("preamble-convert-map.rs"),
+    ),
+    (&["java/util/Optional"], include_str!("preamble-convert-optional.rs")),
+];
+
+/// Classes used by `preamble-iter.rs`, the target of the generated `iter()` methods.
+pub(crate) const ITER: &[&str] = &["java/lang/Object", "java/lang/Iterable", "java/util/Iterator"];
+
+/// Classes used by `preamble-future.rs`, of which `java/util/function/BiConsumer` has to be a proxy.
+const FUTURE: &[&str] = &[
+    "java/lang/Object",
+    "java/util/concurrent/CompletableFuture",
+    "java/util/function/BiConsumer",
+];
+
+pub fn write_preamble(context: &Context, out: &mut impl Write) -> io::Result<()> {
     write!(out, "{}", include_str!("preamble-contents.rs"))?;
     writeln!(out)?;
     writeln!(out)?;
+
+    let included = |class: &str| context.all_classes.contains_key(class);
+    writeln!(out, "pub mod convert {{")?;
+    write!(out, "{}", include_str!("preamble-convert.rs"))?;
+    for (class, boxed) in BOXED {
+        if included(class) {
+            writeln!(out)?;
+            writeln!(out, "    {boxed}")?;
+        }
+    }
+    for (classes, conversions) in CONVERSIONS {
+        if classes.iter().all(|class| included(class)) {
+            writeln!(out)?;
+            write!(out, "{conversions}")?;
+        }
+    }
+    writeln!(out, "}}")?;
+    writeln!(out)?;
+
+    if ITER.iter().all(|class| included(class)) {
+        write!(out, "{}", include_str!("preamble-iter.rs"))?;
+        writeln!(out)?;
+    }
+
+    let proxy = context.config.resolve_class("java/util/function/BiConsumer").proxy;
+    if proxy && FUTURE.iter().all(|class| included(class)) {
+        write!(out, "{}", include_str!("preamble-future.rs"))?;
+        writeln!(out)?;
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_classes::*;
+
+    fn classes(names: &[&str]) -> Vec<TestClass> {
+        let mut classes = vec![
+            TestClass::class("java/lang/Object"),
+            TestClass::class("java/lang/String"),
+            TestClass::class("java/lang/Throwable"),
+        ];
+        classes.extend(names.iter().map(|name| TestClass::class(name)));
+        classes
+    }
+
+    #[test]
+    fn test_conversions_of_included_classes() {
+        let out = generate(
+            "",
+            &classes(&[
+                "java/lang/Integer",
+                "java/util/Map",
+                "java/util/HashMap",
+                "java/util/Optional",
+            ]),
+        );
+
+        assert!(out.contains(&compact("pub enum IntoJavaError<'env>")));
+        assert!(out.contains(&compact("pub enum FromJavaError<'env>")));
+        assert!(out.contains(&compact("Lookup(LookupError),")));
+        assert!(out.contains(&compact(r#"boxed!(i32, Integer, c"(I)Ljava/lang/Integer;""#)));
+        assert!(!out.contains(&compact("boxed!(i64,")));
+        assert!(out.contains(&compact("fn new_map<'env,")));
+        assert!(out.contains(&compact("fn map_entries<'env,")));
+        assert!(out.contains(&compact(
+            "pub fn from_option<'env, T: IntoJava<'env>>(env: Env<'env>, value: Option<T>,) \
+             -> Result<Local<'env, Self>, IntoJavaError<'env>>"
+        )));
+        assert!(!out.contains(&compact("for Vec<T>")));
+        assert!(!out.contains(&compact("for char")));
+    }
+
+    #[test]
+    fn test_conversions_need_all_their_classes() {
+        let out = generate("", &classes(&["java/util/Map", "java/util/List", "java/util/HashSet"]));
+
+        // `HashMap`, `ArrayList` and `Set` are missing.
+        assert!(out.contains(&compact("pub trait IntoJava<'env>")));
+        assert!(!out.contains(&compact("fn new_map<'env,")));
+        assert!(!out.contains(&compact("for Vec<T>")));
+        assert!(!out.contains(&compact("for HashSet<T>")));
+    }
+}
@@ -0,0 +1,193 @@
+//! Class files and bindings generated from them, for the tests of the emitters.
+//!
+//! The classes only have the parts that the emitters look at: names, access flags, supertypes, descriptors and
+//! `throws` clauses. Methods have no code.
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use super::Context;
+use super::java_proxy_bytecode::ConstantPool;
+use crate::config::Config;
+use crate::parser_util::JavaClass;
+
+pub const ACC_PUBLIC: u16 = 0x0001;
+pub const ACC_PRIVATE: u16 = 0x0002;
+pub const ACC_STATIC: u16 = 0x0008;
+pub const ACC_FINAL: u16 = 0x0010;
+pub const ACC_SUPER: u16 = 0x0020;
+pub const ACC_INTERFACE: u16 = 0x0200;
+pub const ACC_ABSTRACT: u16 = 0x0400;
+
+pub struct TestClass {
+    access: u16,
+    name: String,
+    super_class: Option<String>,
+    interfaces: Vec<String>,
+    fields: Vec<(u16, String, String)>,
+    methods: Vec<TestMethod>,
+}
+
+struct TestMethod {
+    access: u16,
+    name: String,
+    descriptor: String,
+    exceptions: Vec<String>,
+}
+
+impl TestClass {
+    /// A public class extending `java.lang.Object`, if it isn't `java.lang.Object` itself.
+    pub fn class(name: &str) -> Self {
+        Self {
+            access: ACC_PUBLIC | ACC_SUPER,
+            name: name.to_owned(),
+            super_class: (name != "java/lang/Object").then(|| "java/lang/Object".to_owned()),
+            interfaces: Vec::new(),
+            fields: Vec::new(),
+            methods: Vec::new(),
+        }
+    }
+
+    /// A public interface.
+    pub fn interface(name: &str) -> Self {
+        Self::class(name).access(ACC_PUBLIC | ACC_INTERFACE | ACC_ABSTRACT)
+    }
+
+    pub fn access(mut self, access: u16) -> Self {
+        self.access = access;
+        self
+    }
+
+    pub fn extends(mut self, super_class: &str) -> Self {
+        self.super_class = Some(super_class.to_owned());
+        self
+    }
+
+    pub fn implements(mut self, interface: &str) -> Self {
+        self.interfaces.push(interface.to_owned());
+        self
+    }
+
+    pub fn field(mut self, access: u16, name: &str, descriptor: &str) -> Self {
+        self.fields.push((access, name.to_owned(), descriptor.to_owned()));
+        self
+    }
+
+    pub fn method(mut self, access: u16, name: &str, descriptor: &str) -> Self {
+        self.methods.push(TestMethod {
+            access,
+            name: name.to_owned(),
+            descriptor: descriptor.to_owned(),
+            exceptions: Vec::new(),
+        });
+        self
+    }
+
+    /// Adds `exception` to the `throws` clause of the last method.
+    pub fn throws(mut self, exception: &str) -> Self {
+        self.methods.last_mut().unwrap().exceptions.push(exception.to_owned());
+        self
+    }
+
+    pub fn write(&self) -> Vec<u8> {
+        let mut cp = ConstantPool::default();
+        let this_class = cp.class(&self.name);
+        let super_class = self.super_class.as_ref().map_or(0, |s| cp.class(s));
+
+        let mut body = Vec::new();
+        body.extend(self.access.to_be_bytes());
+        body.extend(this_class.to_be_bytes());
+        body.extend(super_class.to_be_bytes());
+        body.extend((self.interfaces.len() as u16).to_be_bytes());
+        for interface in &self.interfaces {
+            body.extend(cp.class(interface).to_be_bytes());
+        }
+        body.extend((self.fields.len() as u16).to_be_bytes());
+        for (access, name, descriptor) in &self.fields {
+            body.extend(access.to_be_bytes());
+            body.extend(cp.utf8(name).to_be_bytes());
+            body.extend(cp.utf8(descriptor).to_be_bytes());
+            body.extend(0u16.to_be_bytes());
+        }
+        body.extend((self.methods.len() as u16).to_be_bytes());
+        for method in &self.methods {
+            body.extend(method.access.to_be_bytes());
+            body.extend(cp.utf8(&method.name).to_be_bytes());
+            body.extend(cp.utf8(&method.descriptor).to_be_bytes());
+            if method.exceptions.is_empty() {
+                body.extend(0u16.to_be_bytes());
+                continue;
+            }
+            body.extend(1u16.to_be_bytes());
+            body.extend(cp.utf8("Exceptions").to_be_bytes());
+            body.extend((2 + 2 * method.exceptions.len() as u32).to_be_bytes());
+            body.extend((method.exceptions.len() as u16).to_be_bytes());
+            for exception in &method.exceptions {
+                body.extend(cp.class(exception).to_be_bytes());
+            }
+        }
+        body.extend(0u16.to_be_bytes()); // class attributes
+
+        let mut out = Vec::new();
+        out.extend(0xCAFEBABEu32.to_be_bytes());
+        out.extend(0u16.to_be_bytes());
+        out.extend(52u16.to_be_bytes());
+        out.extend(cp.count.to_be_bytes());
+        out.extend(&cp.bytes);
+        out.extend(body);
+        out
+    }
+}
+
+/// Generates the bindings of `classes` with `config` appended to `java-spaghetti.yaml`, e.g. `rules`, and returns
+/// them without whitespace (see [compact]). All classes are included by default.
+pub fn generate(config: &str, classes: &[TestClass]) -> String {
+    compact(&generate_source(config, classes))
+}
+
+/// Like [generate], but returns the bindings as written, e.g. to compile them.
+pub fn generate_source(config: &str, classes: &[TestClass]) -> String {
+    let yaml = format!("input: []\noutput: bindings.rs\n{config}");
+    let config = Config::read_str(&yaml, Path::new(".")).unwrap();
+    let mut context = Context::new(&config).unwrap();
+    for class in classes {
+        context.add_class(JavaClass::read(class.write()).unwrap()).unwrap();
+    }
+    let mut out = Vec::new();
+    context.write(&mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+/// Removes the whitespace, which depends on how tokens are printed, so that code can be searched for.
+pub fn compact(code: &str) -> String {
+    code.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+/// Runs `cargo check` on a binary crate named `name` that depends on this workspace's `java-spaghetti`, with
+/// `bindings` as its `bindings` module and `main` appended to its `main.rs`. Panics with the errors if it fails.
+pub fn check(name: &str, bindings: &str, main: &str) {
+    let workspace = Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap();
+    let dir = workspace.join("target").join("check-bindings").join(name);
+    fs::create_dir_all(dir.join("src")).unwrap();
+    let runtime = workspace.join("java-spaghetti");
+    let manifest = format!(
+        "[package]\nname = \"{name}\"\nedition = \"2024\"\n\n[dependencies]\njava-spaghetti = {{ path = {runtime:?} }}\n\n[workspace]\n"
+    );
+    fs::write(dir.join("Cargo.toml"), manifest).unwrap();
+    // Reuse the versions locked by the workspace, so that no registry index is needed.
+    fs::copy(workspace.join("Cargo.lock"), dir.join("Cargo.lock")).unwrap();
+    fs::write(dir.join("src").join("bindings.rs"), bindings).unwrap();
+    fs::write(
+        dir.join("src").join("main.rs"),
+        format!("#![feature(arbitrary_self_types)]\n\nmod bindings;\n\n{main}"),
+    )
+    .unwrap();
+
+    let output = Command::new(std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_owned()))
+        .args(["check", "--quiet", "--offline"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}
@@ -1,6 +1,7 @@
 // this must go first because of macros.
 mod util;
 
+mod api_versions;
 mod config;
 mod emit;
 mod identifiers;
@@ -20,7 +21,7 @@ pub fn run(config: impl Into<Config>) -> Result<(), anyhow::Error> {
     let config: Config = config.into();
     println!("output: {}", config.output.display());
 
-    let mut context = emit::Context::new(&config);
+    let mut context = emit::Context::new(&config)?;
     for file in config.input.iter() {
         gather_file(&mut context, file)?;
     }
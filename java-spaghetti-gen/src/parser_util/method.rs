@@ -2,6 +2,8 @@ use cafebabe::MethodAccessFlags;
 use cafebabe::attributes::AttributeData;
 use cafebabe::descriptors::MethodDescriptor;
 
+use super::Id;
+
 pub struct JavaMethod<'a> {
     java: &'a cafebabe::MethodInfo<'a>,
 }
@@ -95,6 +97,19 @@ impl<'a> JavaMethod<'a> {
             .any(|attr| matches!(attr.data, AttributeData::Deprecated))
     }
 
+    /// Classes listed in the `throws` clause, as recorded by the `Exceptions` attribute.
+    pub fn exceptions<'s>(&'s self) -> impl Iterator<Item = Id<'a>> + 's {
+        self.java
+            .attributes
+            .iter()
+            .filter_map(|attr| match &attr.data {
+                AttributeData::Exceptions(exceptions) => Some(exceptions),
+                _ => None,
+            })
+            .flatten()
+            .map(|class| Id(class.as_ref()))
+    }
+
     pub fn descriptor<'s>(&'s self) -> &'a MethodDescriptor<'a> {
         &self.java.descriptor
     }
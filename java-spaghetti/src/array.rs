@@ -1,7 +1,8 @@
-use std::ffi::{CStr, CString};
+use std::ffi::{CStr, CString, c_void};
 use std::marker::PhantomData;
-use std::ops::{Bound, RangeBounds};
+use std::ops::{Bound, Deref, DerefMut, RangeBounds};
 use std::ptr::null_mut;
+use std::slice;
 
 use jni_sys::*;
 
@@ -10,7 +11,8 @@ use crate::{AsArg, Env, JniType, Local, Ref, ReferenceType, ThrowableType};
 /// A Java Array of some POD-like type such as `bool`, `jbyte`, `jchar`, `jshort`, `jint`, `jlong`, `jfloat`, or `jdouble`.
 ///
 /// Thread safety of avoiding [race conditions](https://www.ibm.com/docs/en/sdk-java-technology/8?topic=jni-synchronization)
-/// is not guaranteed. JNI `GetPrimitiveArrayCritical` cannot ensure exclusive access to the array, so it is not used here.
+/// is not guaranteed. JNI `Get{Type}ArrayElements` and `GetPrimitiveArrayCritical` cannot ensure exclusive access to the
+/// array, so the zero-copy [PrimitiveArray::elements] and [PrimitiveArray::critical] are `unsafe`.
 ///
 /// See also [ObjectArray] for arrays of reference types.
 ///
@@ -89,10 +91,167 @@ where
     fn as_vec(self: &Ref<'_, Self>) -> Vec<T> {
         self.get_region_as_vec(0..self.len())
     }
+
+    /// Uses JNI `Get{Type}ArrayElements` to access the elements of the Java array, either pinned in place or as a
+    /// copy depending on the VM. They are released with `Release{Type}ArrayElements` in the given `mode` when the
+    /// guard is dropped.
+    ///
+    /// # Safety
+    ///
+    /// The elements may not be a copy: neither Java nor Rust may access the array by other means, including another
+    /// guard, while the guard is alive.
+    unsafe fn elements<'a>(self: &'a Ref<'_, Self>, mode: ReleaseMode) -> ArrayElements<'a, T>;
+
+    /// Uses JNI `GetPrimitiveArrayCritical` to access the elements of the Java array, which is likely to be pinned in
+    /// place even on VMs that copy the elements for [PrimitiveArray::elements]. They are released with
+    /// `ReleasePrimitiveArrayCritical` in the given `mode` when the guard is dropped.
+    ///
+    /// # Safety
+    ///
+    /// The conditions of [PrimitiveArray::elements] apply. Besides, the VM may pause garbage collection in the critical
+    /// region: until the guard is dropped, the current thread must not call any other JNI function, nor block on
+    /// something another Java thread may be doing.
+    unsafe fn critical<'a>(self: &'a Ref<'_, Self>, mode: ReleaseMode) -> ArrayCritical<'a, T> {
+        let len = self.len();
+        let env = self.env().as_raw();
+        let mut is_copy = false;
+        let elements = unsafe { ((**env).v1_2.GetPrimitiveArrayCritical)(env, self.as_raw(), &mut is_copy) };
+        assert!(!elements.is_null()); // Only sane failure here is an OOM exception
+        ArrayCritical {
+            env,
+            array: self.as_raw(),
+            elements: elements as *mut T,
+            len,
+            is_copy,
+            mode,
+            _array: PhantomData,
+        }
+    }
+}
+
+/// How the elements of an [ArrayElements] or [ArrayCritical] guard are released when it is dropped.
+///
+/// There is no variant for `JNI_COMMIT`, which copies the elements back without freeing them: the guard must free
+/// them exactly once when dropped. Use [ArrayElements::commit] to copy changes back while keeping the guard.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ReleaseMode {
+    /// Copies the elements back into the Java array if they are a copy, then frees them (JNI mode `0`).
+    CommitAndFree,
+    /// Frees the elements without copying them back, discarding changes if they are a copy (`JNI_ABORT`).
+    Abort,
+}
+
+impl ReleaseMode {
+    fn as_jint(self) -> jint {
+        match self {
+            Self::CommitAndFree => 0,
+            Self::Abort => JNI_ABORT,
+        }
+    }
+}
+
+/// The elements of a Java primitive array, obtained with JNI `Get{Type}ArrayElements`. See [PrimitiveArray::elements].
+pub struct ArrayElements<'a, T> {
+    env: *mut JNIEnv,
+    array: jarray,
+    elements: *mut T,
+    len: usize,
+    is_copy: bool,
+    mode: ReleaseMode,
+    release: unsafe extern "system" fn(*mut JNIEnv, jarray, *mut T, jint),
+    _array: PhantomData<&'a ()>,
+}
+
+impl<'a, T> ArrayElements<'a, T> {
+    /// Returns `true` if the elements are a copy of the Java array, i.e. changes only take effect when they are
+    /// committed.
+    pub fn is_copy(&self) -> bool {
+        self.is_copy
+    }
+
+    /// Changes how the elements are released when the guard is dropped.
+    pub fn set_release_mode(&mut self, mode: ReleaseMode) {
+        self.mode = mode;
+    }
+
+    /// Copies the elements back into the Java array if they are a copy, without releasing them (`JNI_COMMIT`).
+    pub fn commit(&mut self) {
+        if self.is_copy {
+            unsafe { (self.release)(self.env, self.array, self.elements, JNI_COMMIT) };
+        }
+    }
+}
+
+impl<'a, T> Deref for ArrayElements<'a, T> {
+    type Target = [T];
+    fn deref(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.elements, self.len) }
+    }
+}
+
+impl<'a, T> DerefMut for ArrayElements<'a, T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        unsafe { slice::from_raw_parts_mut(self.elements, self.len) }
+    }
+}
+
+impl<'a, T> Drop for ArrayElements<'a, T> {
+    fn drop(&mut self) {
+        unsafe { (self.release)(self.env, self.array, self.elements, self.mode.as_jint()) };
+    }
+}
+
+/// The elements of a Java primitive array, obtained with JNI `GetPrimitiveArrayCritical`. See
+/// [PrimitiveArray::critical].
+///
+/// Unlike [ArrayElements], it has no `commit`: HotSpot ends the critical region on any `ReleasePrimitiveArrayCritical`
+/// call, whatever its mode, so the elements can only be released once, when the guard is dropped.
+pub struct ArrayCritical<'a, T> {
+    env: *mut JNIEnv,
+    array: jarray,
+    elements: *mut T,
+    len: usize,
+    is_copy: bool,
+    mode: ReleaseMode,
+    _array: PhantomData<&'a ()>,
+}
+
+impl<'a, T> ArrayCritical<'a, T> {
+    /// Returns `true` if the elements are a copy of the Java array, i.e. changes only take effect when they are
+    /// released with [ReleaseMode::CommitAndFree].
+    pub fn is_copy(&self) -> bool {
+        self.is_copy
+    }
+
+    /// Changes how the elements are released when the guard is dropped.
+    pub fn set_release_mode(&mut self, mode: ReleaseMode) {
+        self.mode = mode;
+    }
+}
+
+impl<'a, T> Deref for ArrayCritical<'a, T> {
+    type Target = [T];
+    fn deref(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.elements, self.len) }
+    }
+}
+
+impl<'a, T> DerefMut for ArrayCritical<'a, T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        unsafe { slice::from_raw_parts_mut(self.elements, self.len) }
+    }
+}
+
+impl<'a, T> Drop for ArrayCritical<'a, T> {
+    fn drop(&mut self) {
+        let env = self.env;
+        let mode = self.mode.as_jint();
+        unsafe { ((**env).v1_2.ReleasePrimitiveArrayCritical)(env, self.array, self.elements as *mut c_void, mode) };
+    }
 }
 
 macro_rules! primitive_array {
-    ($name:ident, $type_str:expr, $type:ident { $new_array:ident $set_region:ident $get_region:ident } ) => {
+    ($name:ident, $type_str:expr, $type:ident { $new_array:ident $set_region:ident $get_region:ident $get_elements:ident $release_elements:ident } ) => {
         /// A [PrimitiveArray] implementation.
         pub enum $name {}
 
@@ -176,18 +335,36 @@ macro_rules! primitive_array {
                     )
                 };
             }
+
+            unsafe fn elements<'a>(self: &'a Ref<'_, Self>, mode: ReleaseMode) -> ArrayElements<'a, $type> {
+                let len = self.len();
+                let env = self.env().as_raw();
+                let mut is_copy = false;
+                let elements = unsafe { ((**env).v1_1.$get_elements)(env, self.as_raw(), &mut is_copy) };
+                assert!(!elements.is_null()); // Only sane failure here is an OOM exception
+                ArrayElements {
+                    env,
+                    array: self.as_raw(),
+                    elements,
+                    len,
+                    is_copy,
+                    mode,
+                    release: unsafe { (**env).v1_1.$release_elements },
+                    _array: PhantomData,
+                }
+            }
         }
     };
 }
 
-primitive_array! { BooleanArray, c"[Z", bool    { NewBooleanArray SetBooleanArrayRegion GetBooleanArrayRegion } }
-primitive_array! { ByteArray,    c"[B", jbyte   { NewByteArray    SetByteArrayRegion    GetByteArrayRegion    } }
-primitive_array! { CharArray,    c"[C", jchar   { NewCharArray    SetCharArrayRegion    GetCharArrayRegion    } }
-primitive_array! { ShortArray,   c"[S", jshort  { NewShortArray   SetShortArrayRegion   GetShortArrayRegion   } }
-primitive_array! { IntArray,     c"[I", jint    { NewIntArray     SetIntArrayRegion     GetIntArrayRegion     } }
-primitive_array! { LongArray,    c"[J", jlong   { NewLongArray    SetLongArrayRegion    GetLongArrayRegion    } }
-primitive_array! { FloatArray,   c"[F", jfloat  { NewFloatArray   SetFloatArrayRegion   GetFloatArrayRegion   } }
-primitive_array! { DoubleArray,  c"[D", jdouble { NewDoubleArray  SetDoubleArrayRegion  GetDoubleArrayRegion  } }
+primitive_array! { BooleanArray, c"[Z", bool    { NewBooleanArray SetBooleanArrayRegion GetBooleanArrayRegion GetBooleanArrayElements ReleaseBooleanArrayElements } }
+primitive_array! { ByteArray,    c"[B", jbyte   { NewByteArray    SetByteArrayRegion    GetByteArrayRegion    GetByteArrayElements    ReleaseByteArrayElements    } }
+primitive_array! { CharArray,    c"[C", jchar   { NewCharArray    SetCharArrayRegion    GetCharArrayRegion    GetCharArrayElements    ReleaseCharArrayElements    } }
+primitive_array! { ShortArray,   c"[S", jshort  { NewShortArray   SetShortArrayRegion   GetShortArrayRegion   GetShortArrayElements   ReleaseShortArrayElements   } }
+primitive_array! { IntArray,     c"[I", jint    { NewIntArray     SetIntArrayRegion     GetIntArrayRegion     GetIntArrayElements     ReleaseIntArrayElements     } }
+primitive_array! { LongArray,    c"[J", jlong   { NewLongArray    SetLongArrayRegion    GetLongArrayRegion    GetLongArrayElements    ReleaseLongArrayElements    } }
+primitive_array! { FloatArray,   c"[F", jfloat  { NewFloatArray   SetFloatArrayRegion   GetFloatArrayRegion   GetFloatArrayElements   ReleaseFloatArrayElements   } }
+primitive_array! { DoubleArray,  c"[D", jdouble { NewDoubleArray  SetDoubleArrayRegion  GetDoubleArrayRegion  GetDoubleArrayElements  ReleaseDoubleArrayElements  } }
 
 /// A Java Array of reference types (classes, interfaces, other arrays, etc.)
 ///
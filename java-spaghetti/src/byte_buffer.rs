@@ -0,0 +1,108 @@
+use std::ffi::c_void;
+use std::ptr::null_mut;
+use std::slice;
+
+use jni_sys::*;
+
+use crate::{Env, Local, Ref, ReferenceType};
+
+/// Direct `java.nio.ByteBuffer` access, to share memory between Rust and Java without copying.
+///
+/// Generated bindings implement this for `java::nio::ByteBuffer`.
+///
+/// Thread safety of avoiding [race conditions](https://www.ibm.com/docs/en/sdk-java-technology/8?topic=jni-synchronization)
+/// is not guaranteed: Java may access the memory of a direct buffer at any time, so the slice views are `unsafe`.
+///
+/// # Safety
+///
+/// `Self` is `java.nio.ByteBuffer`.
+pub unsafe trait ByteBufferType: ReferenceType {
+    /// Uses JNI `NewDirectByteBuffer` to create a direct buffer over `memory`, which has to stay valid for the rest of
+    /// the program as Java may hold on to the buffer indefinitely. Use [Box::leak] to hand over a heap allocation.
+    ///
+    /// Returns `None` if the VM doesn't support JNI access to direct buffers.
+    fn new_direct<'env>(env: Env<'env>, memory: &'static mut [u8]) -> Option<Local<'env, Self>> {
+        unsafe { Self::new_direct_unchecked(env, memory.as_mut_ptr(), memory.len()) }
+    }
+
+    /// Uses JNI `NewDirectByteBuffer` to create a direct buffer over `capacity` bytes starting at `address`.
+    ///
+    /// Returns `None` if the VM doesn't support JNI access to direct buffers.
+    ///
+    /// # Safety
+    ///
+    /// The memory must be valid for reads and writes for as long as Java may use the buffer, and must not be
+    /// accessed through Rust references in the meantime, except through [ByteBufferType::as_direct_slice] and
+    /// [ByteBufferType::as_direct_slice_mut] under their own conditions.
+    unsafe fn new_direct_unchecked<'env>(
+        env: Env<'env>,
+        address: *mut u8,
+        capacity: usize,
+    ) -> Option<Local<'env, Self>> {
+        assert!(capacity <= i64::MAX as usize); // jlong == i64
+        env.require_version(JNI_VERSION_1_4).ok()?;
+        let jnienv = env.as_raw();
+        unsafe {
+            let object = ((**jnienv).v1_4.NewDirectByteBuffer)(jnienv, address as *mut c_void, capacity as jlong);
+            let exception = ((**jnienv).v1_2.ExceptionOccurred)(jnienv);
+            assert!(exception.is_null()); // Only sane exception here is an OOM exception
+            if object.is_null() {
+                None
+            } else {
+                Some(Local::from_raw(env, object))
+            }
+        }
+    }
+
+    /// Uses JNI `GetDirectBufferAddress` to get the start of the memory of a direct buffer.
+    ///
+    /// Returns a null pointer if the buffer is not direct, or if the VM doesn't support JNI access to direct buffers.
+    fn direct_address(self: &Ref<'_, Self>) -> *mut u8 {
+        if self.env().require_version(JNI_VERSION_1_4).is_err() {
+            return null_mut();
+        }
+        let env = self.env().as_raw();
+        unsafe { ((**env).v1_4.GetDirectBufferAddress)(env, self.as_raw()) as *mut u8 }
+    }
+
+    /// Uses JNI `GetDirectBufferCapacity` to get the size of the memory of a direct buffer in bytes.
+    ///
+    /// Returns `None` if the buffer is not direct, or if the VM doesn't support JNI access to direct buffers.
+    fn direct_capacity(self: &Ref<'_, Self>) -> Option<usize> {
+        self.env().require_version(JNI_VERSION_1_4).ok()?;
+        let env = self.env().as_raw();
+        let capacity = unsafe { ((**env).v1_4.GetDirectBufferCapacity)(env, self.as_raw()) };
+        usize::try_from(capacity).ok()
+    }
+
+    /// Views the whole memory of a direct buffer, regardless of its position and limit.
+    ///
+    /// Returns `None` if the buffer is not direct, or if the VM doesn't support JNI access to direct buffers.
+    ///
+    /// # Safety
+    ///
+    /// Neither Java nor Rust may write to the memory while the slice is alive.
+    unsafe fn as_direct_slice<'a>(self: &'a Ref<'_, Self>) -> Option<&'a [u8]> {
+        let (address, capacity) = (self.direct_address(), self.direct_capacity()?);
+        if address.is_null() {
+            return None;
+        }
+        Some(unsafe { slice::from_raw_parts(address, capacity) })
+    }
+
+    /// Views the whole memory of a direct buffer mutably, regardless of its position and limit.
+    ///
+    /// Returns `None` if the buffer is not direct, or if the VM doesn't support JNI access to direct buffers.
+    ///
+    /// # Safety
+    ///
+    /// Neither Java nor Rust may access the memory by other means while the slice is alive.
+    #[allow(clippy::mut_from_ref)]
+    unsafe fn as_direct_slice_mut<'a>(self: &'a Ref<'_, Self>) -> Option<&'a mut [u8]> {
+        let (address, capacity) = (self.direct_address(), self.direct_capacity()?);
+        if address.is_null() {
+            return None;
+        }
+        Some(unsafe { slice::from_raw_parts_mut(address, capacity) })
+    }
+}
@@ -0,0 +1,113 @@
+//! Runtime validations of the `checked` feature, meant for debug builds. Without the feature, they compile to nothing.
+//!
+//! In checked mode:
+//! - An [Env] must be the `JNIEnv` of the current thread whenever a reference is created or a call is made with it.
+//! - [Ref](crate::Ref)s, and so [Local](crate::Local)s, are checked with `IsInstanceOf` against the class of their
+//!   [JniType](crate::JniType) when created. Classes that can't be found are not checked.
+//! - The object or class of every method call and field access of [Env] is checked with `GetObjectRefType` (JNI 1.6),
+//!   catching the references the VM reports as invalid, e.g. deleted references or [Local](crate::Local)s used after
+//!   their frame was popped. How reliably those are detected depends on the VM.
+//!
+//! Any violation panics.
+
+#[cfg(feature = "checked")]
+use std::cell::Cell;
+#[cfg(feature = "checked")]
+use std::ptr::null_mut;
+#[cfg(feature = "checked")]
+use std::sync::atomic::{AtomicPtr, Ordering};
+
+use jni_sys::*;
+
+use crate::{Env, ReferenceType};
+
+/// The VM of all [Env]s, as there can only be one per process.
+#[cfg(feature = "checked")]
+static VM: AtomicPtr<JavaVM> = AtomicPtr::new(null_mut());
+
+#[cfg(feature = "checked")]
+thread_local! {
+    /// Set while checking the type of a reference, as the check creates references itself.
+    static CHECKING_TYPE: Cell<bool> = const { Cell::new(false) };
+}
+
+#[allow(unused_variables)]
+#[allow(unsafe_op_in_unsafe_fn)]
+impl<'env> Env<'env> {
+    /// Panics if this is not the `JNIEnv` of the current thread.
+    #[cfg(feature = "checked")]
+    fn check_thread(self) {
+        unsafe {
+            let mut vm = VM.load(Ordering::Relaxed);
+            if vm.is_null() {
+                vm = self.vm().as_raw();
+                VM.store(vm, Ordering::Relaxed);
+            }
+            let mut env = null_mut();
+            let res = ((**vm).v1_2.GetEnv)(vm, &mut env, JNI_VERSION_1_2);
+            let env = env as *mut JNIEnv;
+            if res == JNI_OK && env == self.as_raw() {
+                return;
+            }
+            let thread = std::thread::current();
+            let thread = match thread.name() {
+                Some(name) => format!("{name:?}"),
+                None => format!("{:?}", thread.id()),
+            };
+            match res {
+                JNI_OK => panic!(
+                    "java-spaghetti: Env {:?} used on thread {thread}, whose Env is {env:?}; an Env must stay on its own thread",
+                    self.as_raw()
+                ),
+                _ => panic!(
+                    "java-spaghetti: Env {:?} used on thread {thread}, which is not attached to the JVM",
+                    self.as_raw()
+                ),
+            }
+        }
+    }
+
+    /// Panics if the VM reports `object` as an invalid JNI reference, e.g. a deleted [Local](crate::Local).
+    /// Also checks the thread of `self`.
+    #[inline(always)]
+    pub(crate) unsafe fn check_ref(self, object: jobject) {
+        #[cfg(feature = "checked")]
+        {
+            self.check_thread();
+            if object.is_null() || self.version() < JNI_VERSION_1_6 {
+                return;
+            }
+            let env = self.as_raw();
+            if let jobjectRefType::JNIInvalidRefType = ((**env).v1_6.GetObjectRefType)(env, object) {
+                panic!(
+                    "java-spaghetti: invalid JNI reference {object:?}; it may have been deleted, or be a Local used \
+                     after its frame was popped"
+                );
+            }
+        }
+    }
+
+    /// Panics if `object` is not an instance of `T`. Also checks the thread of `self`.
+    #[inline(always)]
+    pub(crate) unsafe fn check_instance<T: ReferenceType>(self, object: jobject) {
+        #[cfg(feature = "checked")]
+        {
+            self.check_thread();
+            let env = self.as_raw();
+            if object.is_null() || CHECKING_TYPE.get() || ((**env).v1_2.ExceptionCheck)(env) {
+                return;
+            }
+            CHECKING_TYPE.set(true);
+            let is_instance = T::static_with_jni_type(|class| self.dynamic_class(class))
+                .map(|class| ((**env).v1_2.IsInstanceOf)(env, object, class));
+            CHECKING_TYPE.set(false);
+            if let Ok(false) = is_instance {
+                let class = ((**env).v1_2.GetObjectClass)(env, object);
+                let actual = self.get_class_name(class);
+                ((**env).v1_2.DeleteLocalRef)(env, class);
+                let expected = T::static_with_jni_type(|class| class.to_string_lossy().replace('/', "."));
+                panic!("java-spaghetti: reference to {expected} created for an instance of {actual}");
+            }
+        }
+    }
+}
@@ -0,0 +1,119 @@
+//! Accounting of live [Global](crate::Global) references, enabled by the `debug` feature, to find which types leak
+//! them.
+//!
+//! Each [Global](crate::Global) is counted under the JNI name of its type, e.g. `java/lang/String`, from its creation
+//! until it's dropped or turned into a raw pointer with [Global::into_raw](crate::Global::into_raw). The classes cached
+//! by generated bindings and by [dynamic access](crate::Env::call_method) are counted as `java/lang/Class`. With
+//! [capture_backtraces], each creation also records a [Backtrace], which is slow but points at the leaking code.
+//!
+//! ```rust,no_run
+//! java_spaghetti::debug::capture_backtraces(true);
+//! // ...
+//! let report = java_spaghetti::debug::global_ref_report();
+//! println!("{report}");
+//! assert_eq!(report.count("java/lang/String"), 0);
+//! ```
+
+use std::backtrace::Backtrace;
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+
+use jni_sys::jobject;
+
+struct Entry {
+    jni_type: String,
+    backtrace: Option<Arc<Backtrace>>,
+}
+
+/// Live global references by their raw pointer.
+static GLOBALS: LazyLock<Mutex<HashMap<usize, Entry>>> = LazyLock::new(Default::default);
+static CAPTURE_BACKTRACES: AtomicBool = AtomicBool::new(false);
+
+/// Enables or disables capturing a [Backtrace] when a [Global](crate::Global) is created, regardless of
+/// `RUST_BACKTRACE`. Disabled by default. Only the globals created while enabled have backtraces in the [GlobalRefReport].
+pub fn capture_backtraces(enabled: bool) {
+    CAPTURE_BACKTRACES.store(enabled, Ordering::Relaxed);
+}
+
+/// Counts `object` as a live global reference of `jni_type`, replacing its previous type if already counted.
+pub(crate) fn track(object: jobject, jni_type: &CStr) {
+    let backtrace = match CAPTURE_BACKTRACES.load(Ordering::Relaxed) {
+        true => Some(Arc::new(Backtrace::force_capture())),
+        false => None,
+    };
+    let entry = Entry {
+        jni_type: jni_type.to_string_lossy().into_owned(),
+        backtrace,
+    };
+    GLOBALS.lock().unwrap().insert(object as usize, entry);
+}
+
+/// Stops counting `object`, which is deleted or no longer owned by a [Global](crate::Global).
+pub(crate) fn untrack(object: jobject) {
+    GLOBALS.lock().unwrap().remove(&(object as usize));
+}
+
+/// The live [Global](crate::Global) references of one type, in a [GlobalRefReport].
+#[derive(Debug)]
+pub struct GlobalRefCount {
+    /// The JNI name of the type, e.g. `java/lang/String` or `[I`.
+    pub jni_type: String,
+    pub count: usize,
+    /// The backtraces of the references created while [capture_backtraces] was enabled.
+    pub backtraces: Vec<Arc<Backtrace>>,
+}
+
+/// A snapshot of the live [Global](crate::Global) references, returned by [global_ref_report].
+///
+/// Its `Display` implementation lists the counts, one type per line.
+#[derive(Debug)]
+pub struct GlobalRefReport {
+    /// The counts per type, the largest first.
+    pub types: Vec<GlobalRefCount>,
+}
+
+impl GlobalRefReport {
+    /// The number of live global references of all types.
+    pub fn total(&self) -> usize {
+        self.types.iter().map(|t| t.count).sum()
+    }
+
+    /// The number of live global references of the type with the given JNI name, e.g. `java/lang/String`.
+    pub fn count(&self, jni_type: &str) -> usize {
+        self.types
+            .iter()
+            .find(|t| t.jni_type == jni_type)
+            .map_or(0, |t| t.count)
+    }
+}
+
+impl fmt::Display for GlobalRefReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} live global references", self.total())?;
+        for t in &self.types {
+            write!(f, "\n{:>8} {}", t.count, t.jni_type)?;
+        }
+        Ok(())
+    }
+}
+
+/// Counts the live [Global](crate::Global) references per type.
+pub fn global_ref_report() -> GlobalRefReport {
+    let mut types = HashMap::<&str, GlobalRefCount>::new();
+    let globals = GLOBALS.lock().unwrap();
+    for entry in globals.values() {
+        let t = types.entry(&entry.jni_type).or_insert_with(|| GlobalRefCount {
+            jni_type: entry.jni_type.clone(),
+            count: 0,
+            backtraces: Vec::new(),
+        });
+        t.count += 1;
+        t.backtraces.extend(entry.backtrace.clone());
+    }
+    let mut types: Vec<_> = types.into_values().collect();
+    types.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.jni_type.cmp(&b.jni_type)));
+    GlobalRefReport { types }
+}
@@ -0,0 +1,589 @@
+//! Dynamic access to Java methods and fields by name and JNI descriptor, for classes without generated bindings.
+
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::fmt;
+use std::ptr::null_mut;
+use std::sync::{LazyLock, RwLock};
+
+use jni_sys::*;
+
+use crate::{
+    Env, Global, JFieldID, JMethodID, JavaDebug, JniType, Local, LookupError, Ref, ReferenceType, ThrowableType,
+};
+
+/// Any Java object, for dynamic access without generated bindings. Use [Local::cast] to convert it to a generated type.
+pub enum JObject {}
+
+unsafe impl ReferenceType for JObject {}
+unsafe impl JniType for JObject {
+    fn static_with_jni_type<R>(callback: impl FnOnce(&CStr) -> R) -> R {
+        callback(c"java/lang/Object")
+    }
+}
+
+impl JavaDebug for JObject {
+    /// Writes the name of the class of the object, e.g. `JObject(java.lang.String)`.
+    fn fmt(self: &Ref<'_, Self>, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let env = self.env().as_raw();
+        let name = unsafe {
+            let class = ((**env).v1_2.GetObjectClass)(env, self.as_raw());
+            let name = self.env().get_class_name(class);
+            ((**env).v1_2.DeleteLocalRef)(env, class);
+            name
+        };
+        write!(f, "JObject({name})")
+    }
+}
+
+/// An owned, dynamically typed Java value: an argument, a return value or a field value.
+#[derive(Debug)]
+pub enum JValue<'env> {
+    /// The return value of a `void` method. It is not a valid argument or field value.
+    Void,
+    Boolean(bool),
+    Byte(jbyte),
+    Char(jchar),
+    Short(jshort),
+    Int(jint),
+    Long(jlong),
+    Float(jfloat),
+    Double(jdouble),
+    /// An object or array, or `null`.
+    Object(Option<Local<'env, JObject>>),
+}
+
+impl<'env> JValue<'env> {
+    /// Returns the first character of the JNI descriptors this value is valid for (`L` or `[` for objects).
+    fn descriptor_kind(&self) -> &'static [u8] {
+        match self {
+            Self::Void => b"V",
+            Self::Boolean(_) => b"Z",
+            Self::Byte(_) => b"B",
+            Self::Char(_) => b"C",
+            Self::Short(_) => b"S",
+            Self::Int(_) => b"I",
+            Self::Long(_) => b"J",
+            Self::Float(_) => b"F",
+            Self::Double(_) => b"D",
+            Self::Object(_) => b"L[",
+        }
+    }
+
+    fn as_jvalue(&self) -> jvalue {
+        match self {
+            Self::Void => panic!("void is not a valid argument"),
+            Self::Boolean(z) => jvalue { z: *z },
+            Self::Byte(b) => jvalue { b: *b },
+            Self::Char(c) => jvalue { c: *c },
+            Self::Short(s) => jvalue { s: *s },
+            Self::Int(i) => jvalue { i: *i },
+            Self::Long(j) => jvalue { j: *j },
+            Self::Float(f) => jvalue { f: *f },
+            Self::Double(d) => jvalue { d: *d },
+            Self::Object(l) => jvalue {
+                l: l.as_ref().map_or(null_mut(), |l| l.as_raw()),
+            },
+        }
+    }
+
+    /// Returns the object if this is a non-null [JValue::Object].
+    pub fn into_object(self) -> Option<Local<'env, JObject>> {
+        match self {
+            Self::Object(object) => object,
+            _ => None,
+        }
+    }
+}
+
+macro_rules! jvalue_primitive {
+    ($variant:ident, $type:ty, $as:ident) => {
+        impl<'env> From<$type> for JValue<'env> {
+            fn from(value: $type) -> Self {
+                Self::$variant(value)
+            }
+        }
+
+        impl<'env> JValue<'env> {
+            #[doc = concat!("Returns the value if this is a [JValue::", stringify!($variant), "].")]
+            pub fn $as(&self) -> Option<$type> {
+                match self {
+                    Self::$variant(value) => Some(*value),
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+jvalue_primitive!(Boolean, bool, as_bool);
+jvalue_primitive!(Byte, jbyte, as_byte);
+jvalue_primitive!(Char, jchar, as_char);
+jvalue_primitive!(Short, jshort, as_short);
+jvalue_primitive!(Int, jint, as_int);
+jvalue_primitive!(Long, jlong, as_long);
+jvalue_primitive!(Float, jfloat, as_float);
+jvalue_primitive!(Double, jdouble, as_double);
+
+impl<'env, T: ReferenceType> From<Local<'env, T>> for JValue<'env> {
+    fn from(value: Local<'env, T>) -> Self {
+        // Every reference type is assignable to `java.lang.Object`.
+        Self::Object(Some(unsafe { Local::from_raw(value.env(), value.into_raw()) }))
+    }
+}
+
+impl<'env, T: ReferenceType> From<&Ref<'env, T>> for JValue<'env> {
+    fn from(value: &Ref<'env, T>) -> Self {
+        value.as_local().into()
+    }
+}
+
+impl<'env, T: ReferenceType> From<Option<Local<'env, T>>> for JValue<'env> {
+    fn from(value: Option<Local<'env, T>>) -> Self {
+        value.map_or(Self::Object(None), Self::from)
+    }
+}
+
+/// Returns the JNI descriptor of a type, e.g. `I` for `jint` or `Ljava/lang/String;` for `java::lang::String`.
+pub fn type_descriptor<T: JniType>() -> String {
+    T::static_with_jni_type(|t| {
+        let t = t.to_str().unwrap();
+        if t.len() == 1 || t.starts_with('[') || t.ends_with(';') {
+            t.to_owned()
+        } else {
+            format!("L{t};")
+        }
+    })
+}
+
+/// Builds a JNI method descriptor from the descriptors of its parameter and return types, see [type_descriptor].
+///
+/// ```rust
+/// # use java_spaghetti::*;
+/// # use java_spaghetti::sys::jint;
+/// let descriptor = method_descriptor(&[type_descriptor::<jint>()], &type_descriptor::<()>());
+/// assert_eq!(descriptor.as_c_str(), c"(I)V");
+/// ```
+pub fn method_descriptor(params: &[String], ret: &str) -> CString {
+    CString::new(format!("({}){ret}", params.concat())).unwrap()
+}
+
+/// Splits the first field descriptor off `descriptor`, or returns `None` if it doesn't start with one.
+fn split_type(descriptor: &[u8]) -> Option<(&[u8], &[u8])> {
+    let dims = descriptor.iter().take_while(|b| **b == b'[').count();
+    let end = match descriptor.get(dims)? {
+        b'L' => dims + descriptor[dims..].iter().position(|b| *b == b';')?,
+        b'Z' | b'B' | b'C' | b'S' | b'I' | b'J' | b'F' | b'D' => dims,
+        _ => return None,
+    };
+    Some(descriptor.split_at(end + 1))
+}
+
+fn mismatch(descriptor: &[u8], reason: &'static str) -> LookupError {
+    LookupError::Mismatch {
+        descriptor: CString::new(descriptor).unwrap(),
+        reason,
+    }
+}
+
+#[derive(PartialEq, Eq, Hash)]
+struct MemberKey {
+    is_static: bool,
+    class: CString,
+    name: CString,
+    descriptor: CString,
+}
+
+/// Classes used for dynamic access, never unloaded. Classes are looked up by name like in generated bindings.
+static CLASSES: LazyLock<RwLock<HashMap<CString, Global<JObject>>>> = LazyLock::new(Default::default);
+static METHODS: LazyLock<RwLock<HashMap<MemberKey, JMethodID>>> = LazyLock::new(Default::default);
+static FIELDS: LazyLock<RwLock<HashMap<MemberKey, JFieldID>>> = LazyLock::new(Default::default);
+
+impl<'env> Env<'env> {
+    pub(crate) fn dynamic_class(self, class: &CStr) -> Result<jclass, LookupError> {
+        if let Some(global) = CLASSES.read().unwrap().get(class) {
+            return Ok(global.as_raw());
+        }
+        let global = self.try_require_class_global(class)?;
+        let mut classes = CLASSES.write().unwrap();
+        Ok(classes.entry(class.to_owned()).or_insert(global).as_raw())
+    }
+
+    fn dynamic_method(
+        self,
+        is_static: bool,
+        class: &CStr,
+        name: &CStr,
+        descriptor: &CStr,
+    ) -> Result<(jclass, jmethodID), LookupError> {
+        let jclass = self.dynamic_class(class)?;
+        let key = MemberKey {
+            is_static,
+            class: class.to_owned(),
+            name: name.to_owned(),
+            descriptor: descriptor.to_owned(),
+        };
+        if let Some(method) = METHODS.read().unwrap().get(&key) {
+            return Ok((jclass, method.as_raw()));
+        }
+        let method = unsafe {
+            match is_static {
+                false => self.try_require_method(jclass, name, descriptor)?,
+                true => self.try_require_static_method(jclass, name, descriptor)?,
+            }
+        };
+        METHODS
+            .write()
+            .unwrap()
+            .insert(key, unsafe { JMethodID::from_raw(method) });
+        Ok((jclass, method))
+    }
+
+    fn dynamic_field(
+        self,
+        is_static: bool,
+        class: &CStr,
+        name: &CStr,
+        descriptor: &CStr,
+    ) -> Result<(jclass, jfieldID), LookupError> {
+        let jclass = self.dynamic_class(class)?;
+        let key = MemberKey {
+            is_static,
+            class: class.to_owned(),
+            name: name.to_owned(),
+            descriptor: descriptor.to_owned(),
+        };
+        if let Some(field) = FIELDS.read().unwrap().get(&key) {
+            return Ok((jclass, field.as_raw()));
+        }
+        let field = unsafe {
+            match is_static {
+                false => self.try_require_field(jclass, name, descriptor)?,
+                true => self.try_require_static_field(jclass, name, descriptor)?,
+            }
+        };
+        FIELDS
+            .write()
+            .unwrap()
+            .insert(key, unsafe { JFieldID::from_raw(field) });
+        Ok((jclass, field))
+    }
+
+    /// Checks that `object` is an instance of `class`.
+    fn check_is_instance(self, object: jobject, class: jclass, descriptor: &[u8]) -> Result<(), LookupError> {
+        match unsafe { ((**self.as_raw()).v1_2.IsInstanceOf)(self.as_raw(), object, class) } {
+            true => Ok(()),
+            false => Err(mismatch(descriptor, "object of the wrong class")),
+        }
+    }
+
+    /// Checks that `value` is valid for the field descriptor `descriptor`.
+    fn check_value(self, descriptor: &[u8], value: &JValue) -> Result<(), LookupError> {
+        if split_type(descriptor) != Some((descriptor, b"")) || !value.descriptor_kind().contains(&descriptor[0]) {
+            return Err(mismatch(descriptor, "value of the wrong type"));
+        }
+        if let JValue::Object(Some(object)) = value {
+            let class = match descriptor[0] {
+                b'L' => &descriptor[1..descriptor.len() - 1],
+                _ => descriptor,
+            };
+            let class = self.dynamic_class(&CString::new(class).unwrap())?;
+            self.check_is_instance(object.as_raw(), class, descriptor)?;
+        }
+        Ok(())
+    }
+
+    /// Checks `args` against the method descriptor and converts them, returning the return type descriptor.
+    fn dynamic_args<'d>(self, descriptor: &'d CStr, args: &[JValue]) -> Result<(Vec<jvalue>, &'d [u8]), LookupError> {
+        let bytes = descriptor.to_bytes();
+        let Some(mut rest) = bytes.strip_prefix(b"(") else {
+            return Err(mismatch(bytes, "not a method descriptor"));
+        };
+        let mut jargs = Vec::with_capacity(args.len());
+        for arg in args {
+            let Some((param, r)) = split_type(rest) else {
+                return Err(mismatch(bytes, "too many arguments"));
+            };
+            self.check_value(param, arg)?;
+            jargs.push(arg.as_jvalue());
+            rest = r;
+        }
+        match rest.strip_prefix(b")") {
+            Some(ret) if ret == b"V" || split_type(ret).is_some_and(|(_, r)| r.is_empty()) => Ok((jargs, ret)),
+            Some(_) => Err(mismatch(bytes, "not a method descriptor")),
+            None => Err(mismatch(bytes, "not enough arguments")),
+        }
+    }
+
+    /// Checks that `this` is an instance of `class`, whose JNI name is `name`.
+    fn check_this(self, this: jobject, class: jclass, name: &CStr) -> Result<(), LookupError> {
+        let descriptor = [b"L", name.to_bytes(), b";"].concat();
+        self.check_is_instance(this, class, &descriptor)
+    }
+
+    unsafe fn dynamic_call<E: ThrowableType>(
+        self,
+        this: Option<jobject>,
+        class: jclass,
+        method: jmethodID,
+        ret: &[u8],
+        args: &[jvalue],
+    ) -> Result<JValue<'env>, Local<'env, E>> {
+        let args = args.as_ptr();
+        unsafe {
+            Ok(match (this, ret[0]) {
+                (Some(this), b'V') => self.call_void_method_a(this, method, args).map(|_| JValue::Void)?,
+                (Some(this), b'Z') => JValue::Boolean(self.call_boolean_method_a(this, method, args)?),
+                (Some(this), b'B') => JValue::Byte(self.call_byte_method_a(this, method, args)?),
+                (Some(this), b'C') => JValue::Char(self.call_char_method_a(this, method, args)?),
+                (Some(this), b'S') => JValue::Short(self.call_short_method_a(this, method, args)?),
+                (Some(this), b'I') => JValue::Int(self.call_int_method_a(this, method, args)?),
+                (Some(this), b'J') => JValue::Long(self.call_long_method_a(this, method, args)?),
+                (Some(this), b'F') => JValue::Float(self.call_float_method_a(this, method, args)?),
+                (Some(this), b'D') => JValue::Double(self.call_double_method_a(this, method, args)?),
+                (Some(this), _) => JValue::Object(self.call_object_method_a(this, method, args)?),
+                (None, b'V') => self
+                    .call_static_void_method_a(class, method, args)
+                    .map(|_| JValue::Void)?,
+                (None, b'Z') => JValue::Boolean(self.call_static_boolean_method_a(class, method, args)?),
+                (None, b'B') => JValue::Byte(self.call_static_byte_method_a(class, method, args)?),
+                (None, b'C') => JValue::Char(self.call_static_char_method_a(class, method, args)?),
+                (None, b'S') => JValue::Short(self.call_static_short_method_a(class, method, args)?),
+                (None, b'I') => JValue::Int(self.call_static_int_method_a(class, method, args)?),
+                (None, b'J') => JValue::Long(self.call_static_long_method_a(class, method, args)?),
+                (None, b'F') => JValue::Float(self.call_static_float_method_a(class, method, args)?),
+                (None, b'D') => JValue::Double(self.call_static_double_method_a(class, method, args)?),
+                (None, _) => JValue::Object(self.call_static_object_method_a(class, method, args)?),
+            })
+        }
+    }
+
+    /// Calls an instance method of `class` by name and JNI descriptor, e.g. `c"(I)Ljava/lang/String;"`. IDs are
+    /// cached; an exception thrown by the method is returned like in generated bindings.
+    ///
+    /// Returns a [LookupError] if the class or method is not found, if `this` is not an instance of `class`, or if
+    /// `args` don't match the descriptor.
+    pub fn call_method<E: ThrowableType>(
+        self,
+        this: &Ref<'env, impl ReferenceType>,
+        class: &CStr,
+        name: &CStr,
+        descriptor: &CStr,
+        args: &[JValue<'env>],
+    ) -> Result<Result<JValue<'env>, Local<'env, E>>, LookupError> {
+        let (jclass, method) = self.dynamic_method(false, class, name, descriptor)?;
+        self.check_this(this.as_raw(), jclass, class)?;
+        let (args, ret) = self.dynamic_args(descriptor, args)?;
+        Ok(unsafe { self.dynamic_call(Some(this.as_raw()), jclass, method, ret, &args) })
+    }
+
+    /// Calls a static method of `class` by name and JNI descriptor. See [Env::call_method].
+    pub fn call_static_method<E: ThrowableType>(
+        self,
+        class: &CStr,
+        name: &CStr,
+        descriptor: &CStr,
+        args: &[JValue<'env>],
+    ) -> Result<Result<JValue<'env>, Local<'env, E>>, LookupError> {
+        let (jclass, method) = self.dynamic_method(true, class, name, descriptor)?;
+        let (args, ret) = self.dynamic_args(descriptor, args)?;
+        Ok(unsafe { self.dynamic_call(None, jclass, method, ret, &args) })
+    }
+
+    /// Creates a new object with the constructor of `class` that has the JNI descriptor `descriptor`, e.g. `c"()V"`.
+    /// See [Env::call_method].
+    pub fn new_object<E: ThrowableType>(
+        self,
+        class: &CStr,
+        descriptor: &CStr,
+        args: &[JValue<'env>],
+    ) -> Result<Result<Local<'env, JObject>, Local<'env, E>>, LookupError> {
+        let (jclass, method) = self.dynamic_method(false, class, c"<init>", descriptor)?;
+        let (args, _) = self.dynamic_args(descriptor, args)?;
+        Ok(unsafe { self.new_object_a(jclass, method, args.as_ptr()) })
+    }
+
+    /// Reads an instance field of `class` by name and JNI descriptor, e.g. `c"I"`. IDs are cached.
+    ///
+    /// Returns a [LookupError] if the class or field is not found, or if `this` is not an instance of `class`.
+    pub fn get_field(
+        self,
+        this: &Ref<'env, impl ReferenceType>,
+        class: &CStr,
+        name: &CStr,
+        descriptor: &CStr,
+    ) -> Result<JValue<'env>, LookupError> {
+        let (jclass, field) = self.dynamic_field(false, class, name, descriptor)?;
+        self.check_this(this.as_raw(), jclass, class)?;
+        let this = this.as_raw();
+        unsafe {
+            Ok(match descriptor.to_bytes()[0] {
+                b'Z' => JValue::Boolean(self.get_boolean_field(this, field)),
+                b'B' => JValue::Byte(self.get_byte_field(this, field)),
+                b'C' => JValue::Char(self.get_char_field(this, field)),
+                b'S' => JValue::Short(self.get_short_field(this, field)),
+                b'I' => JValue::Int(self.get_int_field(this, field)),
+                b'J' => JValue::Long(self.get_long_field(this, field)),
+                b'F' => JValue::Float(self.get_float_field(this, field)),
+                b'D' => JValue::Double(self.get_double_field(this, field)),
+                _ => JValue::Object(self.get_object_field(this, field)),
+            })
+        }
+    }
+
+    /// Writes an instance field of `class` by name and JNI descriptor. See [Env::get_field].
+    ///
+    /// Also returns a [LookupError] if `value` doesn't match the descriptor.
+    pub fn set_field(
+        self,
+        this: &Ref<'env, impl ReferenceType>,
+        class: &CStr,
+        name: &CStr,
+        descriptor: &CStr,
+        value: JValue<'env>,
+    ) -> Result<(), LookupError> {
+        let (jclass, field) = self.dynamic_field(false, class, name, descriptor)?;
+        self.check_this(this.as_raw(), jclass, class)?;
+        self.check_value(descriptor.to_bytes(), &value)?;
+        let this = this.as_raw();
+        unsafe {
+            match value {
+                JValue::Boolean(z) => self.set_boolean_field(this, field, z),
+                JValue::Byte(b) => self.set_byte_field(this, field, b),
+                JValue::Char(c) => self.set_char_field(this, field, c),
+                JValue::Short(s) => self.set_short_field(this, field, s),
+                JValue::Int(i) => self.set_int_field(this, field, i),
+                JValue::Long(j) => self.set_long_field(this, field, j),
+                JValue::Float(f) => self.set_float_field(this, field, f),
+                JValue::Double(d) => self.set_double_field(this, field, d),
+                JValue::Object(l) => self.set_object_field::<JObject>(this, field, l.as_ref()),
+                JValue::Void => unreachable!(),
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a static field of `class` by name and JNI descriptor. See [Env::get_field].
+    pub fn get_static_field(self, class: &CStr, name: &CStr, descriptor: &CStr) -> Result<JValue<'env>, LookupError> {
+        let (jclass, field) = self.dynamic_field(true, class, name, descriptor)?;
+        unsafe {
+            Ok(match descriptor.to_bytes()[0] {
+                b'Z' => JValue::Boolean(self.get_static_boolean_field(jclass, field)),
+                b'B' => JValue::Byte(self.get_static_byte_field(jclass, field)),
+                b'C' => JValue::Char(self.get_static_char_field(jclass, field)),
+                b'S' => JValue::Short(self.get_static_short_field(jclass, field)),
+                b'I' => JValue::Int(self.get_static_int_field(jclass, field)),
+                b'J' => JValue::Long(self.get_static_long_field(jclass, field)),
+                b'F' => JValue::Float(self.get_static_float_field(jclass, field)),
+                b'D' => JValue::Double(self.get_static_double_field(jclass, field)),
+                _ => JValue::Object(self.get_static_object_field(jclass, field)),
+            })
+        }
+    }
+
+    /// Writes a static field of `class` by name and JNI descriptor. See [Env::set_field].
+    pub fn set_static_field(
+        self,
+        class: &CStr,
+        name: &CStr,
+        descriptor: &CStr,
+        value: JValue<'env>,
+    ) -> Result<(), LookupError> {
+        let (jclass, field) = self.dynamic_field(true, class, name, descriptor)?;
+        self.check_value(descriptor.to_bytes(), &value)?;
+        unsafe {
+            match value {
+                JValue::Boolean(z) => self.set_static_boolean_field(jclass, field, z),
+                JValue::Byte(b) => self.set_static_byte_field(jclass, field, b),
+                JValue::Char(c) => self.set_static_char_field(jclass, field, c),
+                JValue::Short(s) => self.set_static_short_field(jclass, field, s),
+                JValue::Int(i) => self.set_static_int_field(jclass, field, i),
+                JValue::Long(j) => self.set_static_long_field(jclass, field, j),
+                JValue::Float(f) => self.set_static_float_field(jclass, field, f),
+                JValue::Double(d) => self.set_static_double_field(jclass, field, d),
+                JValue::Object(l) => self.set_static_object_field::<JObject>(jclass, field, l.as_ref()),
+                JValue::Void => unreachable!(),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'env, T: ReferenceType> Ref<'env, T> {
+    /// Calls an instance method of `T` by name and JNI descriptor. See [Env::call_method].
+    pub fn call_method<E: ThrowableType>(
+        &self,
+        name: &CStr,
+        descriptor: &CStr,
+        args: &[JValue<'env>],
+    ) -> Result<Result<JValue<'env>, Local<'env, E>>, LookupError> {
+        T::static_with_jni_type(|class| self.env().call_method(self, class, name, descriptor, args))
+    }
+
+    /// Reads an instance field of `T` by name and JNI descriptor. See [Env::get_field].
+    pub fn get_field(&self, name: &CStr, descriptor: &CStr) -> Result<JValue<'env>, LookupError> {
+        T::static_with_jni_type(|class| self.env().get_field(self, class, name, descriptor))
+    }
+
+    /// Writes an instance field of `T` by name and JNI descriptor. See [Env::set_field].
+    pub fn set_field(&self, name: &CStr, descriptor: &CStr, value: JValue<'env>) -> Result<(), LookupError> {
+        T::static_with_jni_type(|class| self.env().set_field(self, class, name, descriptor, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IntArray;
+
+    #[test]
+    fn test_type_descriptor() {
+        assert_eq!(type_descriptor::<()>(), "V");
+        assert_eq!(type_descriptor::<bool>(), "Z");
+        assert_eq!(type_descriptor::<jint>(), "I");
+        assert_eq!(type_descriptor::<jdouble>(), "D");
+        assert_eq!(type_descriptor::<JObject>(), "Ljava/lang/Object;");
+        assert_eq!(type_descriptor::<IntArray>(), "[I");
+        assert_eq!(
+            method_descriptor(&[type_descriptor::<jint>(), type_descriptor::<JObject>()], "[I").as_c_str(),
+            c"(ILjava/lang/Object;)[I"
+        );
+    }
+
+    #[test]
+    fn test_split_type() {
+        assert_eq!(split_type(b"I"), Some((&b"I"[..], &b""[..])));
+        assert_eq!(split_type(b"JZ)V"), Some((&b"J"[..], &b"Z)V"[..])));
+        assert_eq!(
+            split_type(b"Ljava/lang/String;I)V"),
+            Some((&b"Ljava/lang/String;"[..], &b"I)V"[..]))
+        );
+        assert_eq!(split_type(b"[[B[I"), Some((&b"[[B"[..], &b"[I"[..])));
+        assert_eq!(
+            split_type(b"[Ljava/lang/Object;)V"),
+            Some((&b"[Ljava/lang/Object;"[..], &b")V"[..]))
+        );
+    }
+
+    #[test]
+    fn test_split_type_invalid() {
+        assert_eq!(split_type(b""), None);
+        assert_eq!(split_type(b")V"), None);
+        assert_eq!(split_type(b"V"), None);
+        assert_eq!(split_type(b"X"), None);
+        assert_eq!(split_type(b"[["), None);
+        assert_eq!(split_type(b"Ljava/lang/String"), None);
+    }
+
+    #[test]
+    fn test_mismatch_error() {
+        let error = mismatch(b"(I)V", "not enough arguments");
+        assert_eq!(error.to_string(), r#"not enough arguments for descriptor "(I)V""#);
+    }
+
+    #[test]
+    fn test_jvalue_debug() {
+        assert_eq!(format!("{:?}", JValue::Int(3)), "Int(3)");
+        assert_eq!(format!("{:?}", JValue::Object(None)), "Object(None)");
+    }
+}
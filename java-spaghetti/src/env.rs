@@ -1,12 +1,15 @@
-use std::ffi::CStr;
+use std::ffi::{CStr, CString, c_char};
 use std::marker::PhantomData;
+use std::panic::AssertUnwindSafe;
 use std::ptr::{self, null_mut};
 use std::sync::OnceLock;
 use std::sync::atomic::{AtomicPtr, Ordering};
 
 use jni_sys::*;
 
-use crate::{AsArg, Local, Ref, ReferenceType, StringChars, ThrowableType, VM};
+use crate::{
+    AsArg, Global, JObject, Local, LookupError, Ref, ReferenceType, ThrowableType, VM, VersionError, to_modified_utf8,
+};
 
 /// FFI:  Use **Env** instead of `*const JNIEnv`.  This represents a per-thread Java exection environment.
 ///
@@ -66,6 +69,16 @@ pub struct Env<'env> {
     pd: PhantomData<&'env mut JNIEnv>,
 }
 
+/// The kind of a JNI reference, returned by [Env::get_object_ref_type].
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum RefType {
+    /// Not a valid reference, e.g. a deleted one.
+    Invalid,
+    Local,
+    Global,
+    WeakGlobal,
+}
+
 static CLASS_LOADER: AtomicPtr<_jobject> = AtomicPtr::new(null_mut());
 
 #[allow(clippy::missing_safety_doc)]
@@ -91,6 +104,54 @@ impl<'env> Env<'env> {
         unsafe { VM::from_raw(vm) }
     }
 
+    /// Uses JNI `GetVersion` to get the JNI version supported by the VM, e.g. [JNI_VERSION_1_8].
+    pub fn version(self) -> jint {
+        unsafe { ((**self.env).v1_1.GetVersion)(self.env) }
+    }
+
+    /// Checks that the VM supports at least the given JNI version, and so has the functions it added.
+    pub fn require_version(self, version: jint) -> Result<(), VersionError> {
+        let actual = self.version();
+        match actual >= version {
+            true => Ok(()),
+            false => Err(VersionError {
+                required: version,
+                actual,
+            }),
+        }
+    }
+
+    /// Uses JNI `GetObjectRefType` (JNI 1.6) to tell whether `object` is a local, global or weak global reference.
+    pub fn get_object_ref_type(self, object: &Ref<'_, impl ReferenceType>) -> Result<RefType, VersionError> {
+        self.require_version(JNI_VERSION_1_6)?;
+        let ref_type = unsafe { ((**self.env).v1_6.GetObjectRefType)(self.env, object.as_raw()) };
+        Ok(match ref_type {
+            jobjectRefType::JNIInvalidRefType => RefType::Invalid,
+            jobjectRefType::JNILocalRefType => RefType::Local,
+            jobjectRefType::JNIGlobalRefType => RefType::Global,
+            jobjectRefType::JNIWeakGlobalRefType => RefType::WeakGlobal,
+        })
+    }
+
+    /// Uses JNI `GetModule` (JNI 9) to get the `java.lang.Module` of a class.
+    ///
+    /// # Safety
+    ///
+    /// `class` must be a valid reference to a `java.lang.Class`.
+    pub unsafe fn get_module(self, class: jclass) -> Result<Local<'env, JObject>, VersionError> {
+        self.require_version(JNI_VERSION_9)?;
+        let module = ((**self.env).v9.GetModule)(self.env, class);
+        assert!(!module.is_null());
+        Ok(Local::from_raw(self, module))
+    }
+
+    /// Uses JNI `IsVirtualThread` (JNI 21) to tell whether `thread` is a virtual `java.lang.Thread`. Other objects
+    /// are not virtual threads.
+    pub fn is_virtual_thread(self, thread: &Ref<'_, impl ReferenceType>) -> Result<bool, VersionError> {
+        self.require_version(JNI_VERSION_21)?;
+        Ok(unsafe { ((**self.env).v19.IsVirtualThread)(self.env, thread.as_raw()) })
+    }
+
     // String methods
 
     pub unsafe fn new_string(self, chars: *const jchar, len: jsize) -> jstring {
@@ -111,6 +172,183 @@ impl<'env> Env<'env> {
         ((**self.env).v1_2.ReleaseStringChars)(self.env, string, chars as *const _)
     }
 
+    /// `chars` is NUL-terminated [Modified UTF-8](crate::to_modified_utf8).
+    pub unsafe fn new_string_utf(self, chars: *const c_char) -> jstring {
+        let result = ((**self.env).v1_2.NewStringUTF)(self.env, chars);
+        assert!(!result.is_null());
+        result
+    }
+
+    /// Uses JNI `NewStringUTF` to create a new Java string, converting `string` to Modified UTF-8. Short strings are
+    /// converted on the stack, so creating a short ASCII string doesn't allocate on the Rust side.
+    pub fn new_string_from_str(self, string: &str) -> jstring {
+        let bytes = to_modified_utf8(string);
+        let mut stack = [0u8; 128];
+        if bytes.len() < stack.len() {
+            stack[..bytes.len()].copy_from_slice(&bytes);
+            unsafe { self.new_string_utf(stack.as_ptr() as *const c_char) }
+        } else {
+            let mut heap = Vec::with_capacity(bytes.len() + 1);
+            heap.extend_from_slice(&bytes);
+            heap.push(0);
+            unsafe { self.new_string_utf(heap.as_ptr() as *const c_char) }
+        }
+    }
+
+    /// Returns the length of the Modified UTF-8 encoding of `string` in bytes.
+    pub unsafe fn get_string_utf_length(self, string: jstring) -> jsize {
+        ((**self.env).v1_2.GetStringUTFLength)(self.env, string)
+    }
+
+    pub unsafe fn get_string_utf_chars(self, string: jstring) -> *const c_char {
+        ((**self.env).v1_2.GetStringUTFChars)(self.env, string, null_mut())
+    }
+
+    pub unsafe fn release_string_utf_chars(self, string: jstring, chars: *const c_char) {
+        ((**self.env).v1_2.ReleaseStringUTFChars)(self.env, string, chars)
+    }
+
+    /// Copies the UTF-16 units `start..start + len` of `string` into `buf`. Throws a `StringIndexOutOfBoundsException`
+    /// if the range is out of bounds.
+    pub unsafe fn get_string_region(self, string: jstring, start: jsize, len: jsize, buf: *mut jchar) {
+        ((**self.env).v1_2.GetStringRegion)(self.env, string, start, len, buf)
+    }
+
+    /// Writes the Modified UTF-8 encoding of the UTF-16 units `start..start + len` of `string` into `buf`, which
+    /// needs room for up to `3 * len` bytes. Throws a `StringIndexOutOfBoundsException` if the range is out of bounds.
+    pub unsafe fn get_string_utf_region(self, string: jstring, start: jsize, len: jsize, buf: *mut c_char) {
+        ((**self.env).v1_2.GetStringUTFRegion)(self.env, string, start, len, buf)
+    }
+
+    /// Reads `string` with `GetStringUTFRegion` if it is pure ASCII, which is the case if its UTF-16 length and
+    /// Modified UTF-8 length are the same, without pinning or decoding it. Returns `None` otherwise.
+    pub unsafe fn get_string_ascii(self, string: jstring) -> Option<String> {
+        let len = self.get_string_length(string);
+        if self.get_string_utf_length(string) != len {
+            return None;
+        }
+        let mut buf = vec![0u8; len as usize + 1]; // Some VMs add a NUL terminator.
+        self.get_string_utf_region(string, 0, len, buf.as_mut_ptr() as *mut c_char);
+        buf.truncate(len as usize);
+        String::from_utf8(buf).ok()
+    }
+
+    // Local Reference Management
+
+    /// Uses JNI `EnsureLocalCapacity` to make sure that at least `capacity` more local references can be created
+    /// in the current local frame. Returns the `OutOfMemoryError` if the VM cannot reserve them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `EnsureLocalCapacity` fails without throwing an exception.
+    pub fn ensure_local_capacity<E: ThrowableType>(self, capacity: usize) -> Result<(), Local<'env, E>> {
+        assert!(capacity <= i32::MAX as usize); // jint == i32
+        let res = unsafe { ((**self.env).v1_2.EnsureLocalCapacity)(self.env, capacity as jint) };
+        if res == JNI_OK {
+            return Ok(());
+        }
+        self.exception_check()?;
+        panic!("EnsureLocalCapacity({capacity}) failed with error {res} and no pending exception");
+    }
+
+    /// Runs `f` inside a new local reference frame created with JNI `PushLocalFrame`, which can hold at least
+    /// `capacity` local references. All local references created in the frame are freed by `PopLocalFrame`
+    /// when `f` returns (or panics).
+    ///
+    /// `f` receives an [Env] with a new lifetime, so that [Local]s created with it cannot escape the frame:
+    ///
+    /// ```compile_fail
+    /// # use java_spaghetti::*;
+    /// # fn example<'env, T: ReferenceType>(env: Env<'env>, global: &Global<T>) {
+    /// // error: lifetime may not live long enough
+    /// let local = unsafe { env.with_local_frame(1, |env| global.as_local(env)) };
+    /// # }
+    /// ```
+    ///
+    /// Use [Env::with_local_frame_returning] to carry a result reference out of the frame.
+    ///
+    /// # Safety
+    ///
+    /// Inside `f`, local references must only be created with the `Env` given to `f`, or with references obtained
+    /// from it. References created with the outer `Env`, e.g. by a [Ref] or [Local] from outside of the frame, are
+    /// freed by `PopLocalFrame` as well, but keep the outer lifetime; using them after `f` returns is undefined
+    /// behavior. `Env` and references are `Copy` or borrowable, so this can't be enforced by the borrow checker.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the VM is out of memory for `capacity` local references.
+    pub unsafe fn with_local_frame<T>(self, capacity: usize, f: impl for<'frame> FnOnce(Env<'frame>) -> T) -> T {
+        let frame = unsafe { self.push_local_frame(capacity) };
+        let result = f(unsafe { Env::from_raw(self.env) });
+        drop(frame);
+        result
+    }
+
+    /// Like [Env::with_local_frame], but moves the one reference returned by `f`, either a result or an exception,
+    /// into the outer frame (the second argument of JNI `PopLocalFrame`). The return type matches the one of
+    /// generated methods, so they can be called inside the frame with `?`.
+    ///
+    /// # Safety
+    ///
+    /// The same as for [Env::with_local_frame].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the VM is out of memory for `capacity` local references.
+    pub unsafe fn with_local_frame_returning<R: ReferenceType, E: ReferenceType>(
+        self,
+        capacity: usize,
+        f: impl for<'frame> FnOnce(Env<'frame>) -> Result<Option<Local<'frame, R>>, Local<'frame, E>>,
+    ) -> Result<Option<Local<'env, R>>, Local<'env, E>> {
+        let frame = unsafe { self.push_local_frame(capacity) };
+        let result = f(unsafe { Env::from_raw(self.env) });
+        std::mem::forget(frame); // The frame is popped here instead.
+
+        let (object, is_ok) = match result {
+            Ok(Some(local)) => (local.into_raw(), true),
+            Ok(None) => (null_mut(), true),
+            Err(exception) => (exception.into_raw(), false),
+        };
+        let object = unsafe { ((**self.env).v1_2.PopLocalFrame)(self.env, object) };
+        match (object.is_null(), is_ok) {
+            (true, _) => Ok(None),
+            (false, true) => Ok(Some(unsafe { Local::from_raw(self, object) })),
+            (false, false) => Err(unsafe { Local::from_raw(self, object) }),
+        }
+    }
+
+    /// Calls JNI `PushLocalFrame`. The returned guard calls `PopLocalFrame` with a null result when dropped.
+    unsafe fn push_local_frame(self, capacity: usize) -> LocalFrame {
+        assert!(capacity <= i32::MAX as usize); // jint == i32
+        let res = ((**self.env).v1_2.PushLocalFrame)(self.env, capacity as jint);
+        if res != JNI_OK {
+            ((**self.env).v1_2.ExceptionClear)(self.env);
+            panic!("PushLocalFrame({capacity}) failed: out of memory");
+        }
+        LocalFrame { env: self.env }
+    }
+
+    // Native Method Registration
+
+    /// Binds native methods of `class` to Rust functions with JNI `RegisterNatives`, so that they don't have to be
+    /// exported as `Java_...` symbols. Returns the `NoSuchMethodError` if a method is not found or is not native.
+    ///
+    /// # Safety
+    ///
+    /// - `class` must be a valid reference to a class object.
+    /// - `name` and `signature` of each method must point to NUL-terminated "Modified UTF-8" strings.
+    /// - `fnPtr` of each method must be an `extern "system"` function with arguments and return type matching
+    ///   `signature`, taking a `JNIEnv` pointer and the object (or class, for static methods) first.
+    pub unsafe fn register_natives<E: ThrowableType>(
+        self,
+        class: jclass,
+        methods: &[JNINativeMethod],
+    ) -> Result<(), Local<'env, E>> {
+        assert!(methods.len() <= i32::MAX as usize); // jint == i32
+        let res = ((**self.env).v1_2.RegisterNatives)(self.env, class, methods.as_ptr(), methods.len() as jint);
+        if res == JNI_OK { Ok(()) } else { self.exception_check() }
+    }
+
     // Query Methods
 
     /// Set a custom class loader to use instead of JNI `FindClass` calls.
@@ -141,63 +379,202 @@ impl<'env> Env<'env> {
         CLASS_LOADER.store(classloader, Ordering::Relaxed);
     }
 
-    /// Checks if an exception has occurred; if occurred, it clears the exception to make the next
-    /// JNI call possible, then it returns the exception as an `Err`.
+    // Exception Methods
+
+    /// Uses JNI `ExceptionCheck` to test whether an exception is pending, without creating a local reference
+    /// to it or clearing it.
+    pub fn exception_pending(self) -> bool {
+        unsafe { ((**self.env).v1_2.ExceptionCheck)(self.env) }
+    }
+
+    /// Takes the pending exception, if any: it clears the exception to make the next JNI call possible,
+    /// then it returns the exception object.
     ///
-    /// XXX: Make this method public after making sure that it has a proper name.
-    /// Note that there is `ExceptionCheck` in JNI functions, which does not create a
-    /// local reference to the exception object.
-    pub(crate) fn exception_check<E: ThrowableType>(self) -> Result<(), Local<'env, E>> {
+    /// `E` is not checked, it should be `Throwable` or any other type all exceptions are assignable to.
+    pub fn exception_take<E: ThrowableType>(self) -> Option<Local<'env, E>> {
         unsafe {
             let exception = ((**self.env).v1_2.ExceptionOccurred)(self.env);
             if exception.is_null() {
-                Ok(())
+                None
             } else {
                 ((**self.env).v1_2.ExceptionClear)(self.env);
-                Err(Local::from_raw(self, exception))
+                #[cfg(feature = "tracing")]
+                if tracing::enabled!(tracing::Level::DEBUG) {
+                    let class = ((**self.env).v1_2.GetObjectClass)(self.env, exception);
+                    tracing::debug!(exception = self.get_class_name(class), "Java exception");
+                    ((**self.env).v1_2.DeleteLocalRef)(self.env, class);
+                }
+                Some(Local::from_raw(self, exception))
             }
         }
     }
 
-    unsafe fn exception_to_string(self, exception: jobject) -> String {
-        static METHOD_GET_MESSAGE: OnceLock<usize> = OnceLock::new();
-        let throwable_get_message = *METHOD_GET_MESSAGE.get_or_init(|| {
-            // use JNI FindClass to avoid infinte recursion.
-            let throwable_class = self.require_class_jni(c"java/lang/Throwable");
-            let method = self.require_method(throwable_class, c"getMessage", c"()Ljava/lang/String;");
-            ((**self.env).v1_2.DeleteLocalRef)(self.env, throwable_class);
-            method.addr()
-        }) as jmethodID; // it is a global ID
-
-        let message =
-            ((**self.env).v1_2.CallObjectMethodA)(self.env, exception, throwable_get_message, ptr::null_mut());
-        let e2: *mut _jobject = ((**self.env).v1_2.ExceptionOccurred)(self.env);
-        if !e2.is_null() {
-            ((**self.env).v1_2.ExceptionClear)(self.env);
-            panic!("exception happened calling Throwable.getMessage()");
+    /// Checks if an exception has occurred; if occurred, it clears the exception to make the next
+    /// JNI call possible, then it returns the exception as an `Err`. This is what generated bindings do
+    /// after each JNI call. See [Env::exception_take].
+    pub fn exception_check<E: ThrowableType>(self) -> Result<(), Local<'env, E>> {
+        match self.exception_take() {
+            None => Ok(()),
+            Some(exception) => Err(exception),
+        }
+    }
+
+    /// Uses JNI `ExceptionDescribe` to print the pending exception and its backtrace to the system error-reporting
+    /// channel (`stderr`, or logcat on Android). The exception is cleared as a side effect.
+    pub fn exception_describe(self) {
+        unsafe { ((**self.env).v1_2.ExceptionDescribe)(self.env) }
+    }
+
+    /// Uses JNI `ExceptionClear` to clear the pending exception, if any.
+    pub fn exception_clear(self) {
+        unsafe { ((**self.env).v1_2.ExceptionClear)(self.env) }
+    }
+
+    /// Makes `throwable` the pending exception with JNI `Throw`. It will be thrown in Java as soon as the
+    /// native method returns.
+    pub fn throw<T: ReferenceType>(self, throwable: &Ref<T>) {
+        let res = unsafe { ((**self.env).v1_2.Throw)(self.env, throwable.as_raw()) };
+        assert_eq!(res, JNI_OK);
+    }
+
+    /// Constructs a new exception of the given class (e.g. `c"java/lang/IllegalStateException"`) with its
+    /// `(String)` constructor and makes it the pending exception.
+    ///
+    /// Unlike JNI `ThrowNew`, which takes a "Modified UTF-8" message, any Rust string can be passed as `message`.
+    ///
+    /// If the exception can't be constructed, the exception thrown by the constructor (e.g. `OutOfMemoryError`)
+    /// becomes the pending exception instead.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the class or its `(String)` constructor is not found.
+    pub fn throw_new(self, class: &CStr, message: &str) {
+        unsafe {
+            let class = self.require_class(class);
+            let constructor = self.require_method(class, c"<init>", c"(Ljava/lang/String;)V");
+            let chars = message.encode_utf16().collect::<Vec<_>>();
+            let message = self.new_string(chars.as_ptr(), chars.len() as jsize);
+            let args = [jvalue { l: message }];
+            let exception = ((**self.env).v1_2.NewObjectA)(self.env, class, constructor, args.as_ptr());
+            if !exception.is_null() {
+                let res = ((**self.env).v1_2.Throw)(self.env, exception);
+                assert_eq!(res, JNI_OK);
+                ((**self.env).v1_2.DeleteLocalRef)(self.env, exception);
+            }
+            ((**self.env).v1_2.DeleteLocalRef)(self.env, message);
+            ((**self.env).v1_2.DeleteLocalRef)(self.env, class);
+        }
+    }
+
+    /// Runs `f`, typically the body of a native method, catching any Rust panic before it can unwind into Java.
+    ///
+    /// On panic, the pending exception (if any) is cleared, a new exception of the given class (e.g.
+    /// `c"java/lang/RuntimeException"`) is thrown with the panic message, and `R::default()` is returned. The VM is
+    /// aborted with [Env::fatal_error] if throwing the exception panics as well. Generated proxy entry points use this.
+    ///
+    /// `f` is treated as unwind safe: the native method returns right after, so broken invariants can only
+    /// be observed through state shared with other calls.
+    pub fn catch_unwind<R: Default>(self, class: &CStr, f: impl FnOnce() -> R) -> R {
+        let payload = match std::panic::catch_unwind(AssertUnwindSafe(f)) {
+            Ok(res) => return res,
+            Err(payload) => payload,
+        };
+        let message = match payload.downcast_ref::<&str>() {
+            Some(message) => message,
+            None => match payload.downcast_ref::<String>() {
+                Some(message) => message.as_str(),
+                None => "Rust panic",
+            },
+        };
+        let thrown = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            self.exception_clear();
+            self.throw_new(class, message);
+        }));
+        if thrown.is_err() {
+            self.fatal_error(&format!("panic while throwing the Rust panic \"{message}\" to Java"));
         }
+        R::default()
+    }
 
-        StringChars::from_env_jstring(self, message).to_string_lossy()
+    /// Uses JNI `FatalError` to abort the VM with the given message. This function never returns.
+    pub fn fatal_error(self, message: &str) -> ! {
+        let message = CString::new(message.replace('\0', "\\0")).unwrap();
+        unsafe { ((**self.env).v1_2.FatalError)(self.env, message.as_ptr()) }
+    }
+
+    /// Uses JNI `DefineClass` to define a class from the contents of a `.class` file, such as the proxy classes
+    /// embedded by `java-spaghetti-gen`. Returns the new class as a local reference, or the exception thrown
+    /// by the VM (e.g. `ClassFormatError`, or `LinkageError` if the class is already defined).
+    ///
+    /// The class is defined in the class loader set by [Env::set_class_loader], or the system class loader, so
+    /// that it can see the application classes it refers to.
+    ///
+    /// Android doesn't support `DefineClass`, classes must be loaded from DEX files there instead.
+    ///
+    /// # Safety
+    ///
+    /// `class` must be the binary name of the class in `bytes`, e.g. `c"com/example/Foo"`.
+    pub unsafe fn define_class<E: ThrowableType>(self, class: &CStr, bytes: &[u8]) -> Result<jclass, Local<'env, E>> {
+        assert!(bytes.len() <= i32::MAX as usize); // jsize == i32
+        let mut classloader = CLASS_LOADER.load(Ordering::Relaxed);
+        let mut system_classloader = null_mut();
+        if classloader.is_null() {
+            let (cl_class, method) = self.require_class_static_method(
+                c"java/lang/ClassLoader",
+                c"getSystemClassLoader",
+                c"()Ljava/lang/ClassLoader;",
+            );
+            system_classloader = ((**self.env).v1_2.CallStaticObjectMethodA)(self.env, cl_class, method, ptr::null());
+            ((**self.env).v1_2.DeleteLocalRef)(self.env, cl_class);
+            self.exception_check()?;
+            classloader = system_classloader;
+        }
+        let res = ((**self.env).v1_2.DefineClass)(
+            self.env,
+            class.as_ptr(),
+            classloader,
+            bytes.as_ptr() as *const jbyte,
+            bytes.len() as jsize,
+        );
+        if !system_classloader.is_null() {
+            ((**self.env).v1_2.DeleteLocalRef)(self.env, system_classloader);
+        }
+        self.exception_check()?;
+        assert!(!res.is_null());
+        Ok(res)
     }
 
     /// Note: the returned `jclass` is actually a new local reference of the class object.
+    ///
+    /// Panics if the class is not found. See [Env::try_require_class].
     pub unsafe fn require_class(self, class: &CStr) -> jclass {
+        self.try_require_class(class).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Looks up a class with JNI `FindClass`, then with the class loader set by [Env::set_class_loader] (if any).
+    /// Returns a [LookupError] instead of panicking if it's not found.
+    ///
+    /// Note: the returned `jclass` is actually a new local reference of the class object.
+    pub unsafe fn try_require_class(self, class: &CStr) -> Result<jclass, LookupError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("jni_lookup", class = %class.to_string_lossy()).entered();
+
         // First try with JNI FindClass.
         let c = ((**self.env).v1_2.FindClass)(self.env, class.as_ptr());
         let exception: *mut _jobject = ((**self.env).v1_2.ExceptionOccurred)(self.env);
         if !exception.is_null() {
             ((**self.env).v1_2.ExceptionClear)(self.env);
+            ((**self.env).v1_2.DeleteLocalRef)(self.env, exception);
         }
         if !c.is_null() {
-            return c;
+            return Ok(c);
         }
 
         // If class is not found and we have a classloader set, try that.
         let classloader = CLASS_LOADER.load(Ordering::Relaxed);
         if !classloader.is_null() {
             let chars = class
-                .to_str()
-                .unwrap()
+                .to_string_lossy()
                 .replace('/', ".")
                 .encode_utf16()
                 .collect::<Vec<_>>();
@@ -216,24 +593,45 @@ impl<'env> Env<'env> {
             let args = [jvalue { l: string }];
             let result: *mut _jobject =
                 ((**self.env).v1_2.CallObjectMethodA)(self.env, classloader, cl_method, args.as_ptr());
+            ((**self.env).v1_2.DeleteLocalRef)(self.env, string);
             let exception: *mut _jobject = ((**self.env).v1_2.ExceptionOccurred)(self.env);
             if !exception.is_null() {
+                // Usually a `ClassNotFoundException`.
                 ((**self.env).v1_2.ExceptionClear)(self.env);
-                panic!(
-                    "exception happened calling loadClass(): {}",
-                    self.exception_to_string(exception)
-                );
-            } else if result.is_null() {
-                panic!("loadClass() returned null");
+                ((**self.env).v1_2.DeleteLocalRef)(self.env, exception);
+            } else if !result.is_null() {
+                return Ok(result as jclass);
             }
+        }
 
-            ((**self.env).v1_2.DeleteLocalRef)(self.env, string);
+        Err(LookupError::Class {
+            class: class.to_owned(),
+        })
+    }
 
-            return result as jclass;
-        }
+    /// Panics if the class is not found. See [Env::try_require_class_global].
+    pub fn require_class_global(self, class: &CStr) -> Global<JObject> {
+        self.try_require_class_global(class).unwrap_or_else(|e| panic!("{e}"))
+    }
 
-        // If neither found the class, panic.
-        panic!("couldn't load class {class:?}");
+    /// Looks up a class like [Env::try_require_class], returning a global reference to be cached, e.g. by generated
+    /// bindings. See [Env::class_global].
+    pub fn try_require_class_global(self, class: &CStr) -> Result<Global<JObject>, LookupError> {
+        Ok(unsafe { self.class_global(self.try_require_class(class)?) })
+    }
+
+    /// Turns a local reference to a class into a global reference to be cached. With the `debug` feature, it's
+    /// counted as a `java/lang/Class` global.
+    ///
+    /// # Safety
+    ///
+    /// `class` must be an owned local reference to a `java.lang.Class`, like the ones returned by
+    /// [Env::require_class]. It's deleted.
+    pub unsafe fn class_global(self, class: jclass) -> Global<JObject> {
+        let global = Local::<JObject>::from_raw(self, class).as_global();
+        #[cfg(feature = "debug")]
+        crate::debug::track(global.as_raw(), c"java/lang/Class");
+        global
     }
 
     unsafe fn require_class_jni(self, class: &CStr) -> jclass {
@@ -245,8 +643,8 @@ impl<'env> Env<'env> {
         res
     }
 
-    // used only for debugging
-    unsafe fn get_class_name(self, class: jclass) -> String {
+    // used for error messages
+    pub(crate) unsafe fn get_class_name(self, class: jclass) -> String {
         let classclass = self.require_class_jni(c"java/lang/Class");
 
         // don't use self.require_method() here to avoid recursion!
@@ -263,7 +661,13 @@ impl<'env> Env<'env> {
         }
 
         let string = ((**self.env).v1_2.CallObjectMethod)(self.env, class, method);
+        if ((**self.env).v1_2.ExceptionCheck)(self.env) {
+            ((**self.env).v1_2.ExceptionClear)(self.env);
+            ((**self.env).v1_2.DeleteLocalRef)(self.env, classclass);
+            return "??? (getName threw an exception)".to_string();
+        }
         if string.is_null() {
+            ((**self.env).v1_2.DeleteLocalRef)(self.env, classclass);
             return "??? (getName returned null string)".to_string();
         }
         let chars = ((**self.env).v1_2.GetStringUTFChars)(self.env, string, ptr::null_mut());
@@ -283,49 +687,138 @@ impl<'env> Env<'env> {
         res
     }
 
+    /// Panics if the method is not found. See [Env::try_require_method].
     pub unsafe fn require_method(self, class: jclass, method: &CStr, descriptor: &CStr) -> jmethodID {
+        self.try_require_method(class, method, descriptor)
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Looks up an instance method with JNI `GetMethodID`, returning a [LookupError] if it's not found.
+    pub unsafe fn try_require_method(
+        self,
+        class: jclass,
+        method: &CStr,
+        descriptor: &CStr,
+    ) -> Result<jmethodID, LookupError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(
+            "jni_lookup",
+            member = %method.to_string_lossy(),
+            descriptor = %descriptor.to_string_lossy()
+        )
+        .entered();
+
         let res = ((**self.env).v1_2.GetMethodID)(self.env, class, method.as_ptr(), descriptor.as_ptr());
         if res.is_null() {
             ((**self.env).v1_2.ExceptionClear)(self.env);
-            let class_name = self.get_class_name(class);
-            panic!("could not find method {method:?} {descriptor:?} on class {class_name:?}");
+            return Err(LookupError::Method {
+                class: self.get_class_name(class),
+                name: method.to_owned(),
+                descriptor: descriptor.to_owned(),
+            });
         }
-        res
+        Ok(res)
     }
 
+    /// Panics if the method is not found. See [Env::try_require_static_method].
     pub unsafe fn require_static_method(self, class: jclass, method: &CStr, descriptor: &CStr) -> jmethodID {
+        self.try_require_static_method(class, method, descriptor)
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Looks up a static method with JNI `GetStaticMethodID`, returning a [LookupError] if it's not found.
+    pub unsafe fn try_require_static_method(
+        self,
+        class: jclass,
+        method: &CStr,
+        descriptor: &CStr,
+    ) -> Result<jmethodID, LookupError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(
+            "jni_lookup",
+            member = %method.to_string_lossy(),
+            descriptor = %descriptor.to_string_lossy()
+        )
+        .entered();
+
         let res = ((**self.env).v1_2.GetStaticMethodID)(self.env, class, method.as_ptr(), descriptor.as_ptr());
         if res.is_null() {
             ((**self.env).v1_2.ExceptionClear)(self.env);
-            let class_name = self.get_class_name(class);
-            panic!("could not find static method {method:?} {descriptor:?} on class {class_name:?}");
+            return Err(LookupError::StaticMethod {
+                class: self.get_class_name(class),
+                name: method.to_owned(),
+                descriptor: descriptor.to_owned(),
+            });
         }
-        res
+        Ok(res)
     }
 
+    /// Panics if the field is not found. See [Env::try_require_field].
     pub unsafe fn require_field(self, class: jclass, field: &CStr, descriptor: &CStr) -> jfieldID {
+        self.try_require_field(class, field, descriptor)
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Looks up an instance field with JNI `GetFieldID`, returning a [LookupError] if it's not found.
+    pub unsafe fn try_require_field(
+        self,
+        class: jclass,
+        field: &CStr,
+        descriptor: &CStr,
+    ) -> Result<jfieldID, LookupError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(
+            "jni_lookup",
+            member = %field.to_string_lossy(),
+            descriptor = %descriptor.to_string_lossy()
+        )
+        .entered();
+
         let res = ((**self.env).v1_2.GetFieldID)(self.env, class, field.as_ptr(), descriptor.as_ptr());
         if res.is_null() {
             ((**self.env).v1_2.ExceptionClear)(self.env);
-            let class_name = self.get_class_name(class);
-            panic!("could not find field {field:?} {descriptor:?} on class {class_name:?}");
+            return Err(LookupError::Field {
+                class: self.get_class_name(class),
+                name: field.to_owned(),
+                descriptor: descriptor.to_owned(),
+            });
         }
-        res
+        Ok(res)
     }
 
+    /// Panics if the field is not found. See [Env::try_require_static_field].
     pub unsafe fn require_static_field(self, class: jclass, field: &CStr, descriptor: &CStr) -> jfieldID {
+        self.try_require_static_field(class, field, descriptor)
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Looks up a static field with JNI `GetStaticFieldID`, returning a [LookupError] if it's not found.
+    pub unsafe fn try_require_static_field(
+        self,
+        class: jclass,
+        field: &CStr,
+        descriptor: &CStr,
+    ) -> Result<jfieldID, LookupError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(
+            "jni_lookup",
+            member = %field.to_string_lossy(),
+            descriptor = %descriptor.to_string_lossy()
+        )
+        .entered();
+
         let res = ((**self.env).v1_2.GetStaticFieldID)(self.env, class, field.as_ptr(), descriptor.as_ptr());
         if res.is_null() {
             ((**self.env).v1_2.ExceptionClear)(self.env);
-            let class_name = self.get_class_name(class);
-            panic!("could not find static field {field:?} {descriptor:?} on class {class_name:?}");
+            return Err(LookupError::StaticField {
+                class: self.get_class_name(class),
+                name: field.to_owned(),
+                descriptor: descriptor.to_owned(),
+            });
         }
-        res
+        Ok(res)
     }
 
-    // Multi-Query Methods
-    // XXX: Remove these unused functions.
-
     pub unsafe fn require_class_method(self, class: &CStr, method: &CStr, descriptor: &CStr) -> (jclass, jmethodID) {
         let class = self.require_class(class);
         (class, self.require_method(class, method, descriptor))
@@ -364,6 +857,7 @@ impl<'env> Env<'env> {
         method: jmethodID,
         args: *const jvalue,
     ) -> Result<Local<'env, R>, Local<'env, E>> {
+        self.check_ref(class);
         let result = ((**self.env).v1_2.NewObjectA)(self.env, class, method, args);
         self.exception_check()?;
         assert!(!result.is_null());
@@ -378,6 +872,7 @@ impl<'env> Env<'env> {
         method: jmethodID,
         args: *const jvalue,
     ) -> Result<Option<Local<'env, R>>, Local<'env, E>> {
+        self.check_ref(this);
         let result = ((**self.env).v1_2.CallObjectMethodA)(self.env, this, method, args);
         self.exception_check()?;
         if result.is_null() {
@@ -393,6 +888,7 @@ impl<'env> Env<'env> {
         method: jmethodID,
         args: *const jvalue,
     ) -> Result<bool, Local<'env, E>> {
+        self.check_ref(this);
         let result = ((**self.env).v1_2.CallBooleanMethodA)(self.env, this, method, args);
         self.exception_check()?;
         Ok(result != JNI_FALSE)
@@ -404,6 +900,7 @@ impl<'env> Env<'env> {
         method: jmethodID,
         args: *const jvalue,
     ) -> Result<jbyte, Local<'env, E>> {
+        self.check_ref(this);
         let result = ((**self.env).v1_2.CallByteMethodA)(self.env, this, method, args);
         self.exception_check()?;
         Ok(result)
@@ -415,6 +912,7 @@ impl<'env> Env<'env> {
         method: jmethodID,
         args: *const jvalue,
     ) -> Result<jchar, Local<'env, E>> {
+        self.check_ref(this);
         let result = ((**self.env).v1_2.CallCharMethodA)(self.env, this, method, args);
         self.exception_check()?;
         Ok(result)
@@ -426,6 +924,7 @@ impl<'env> Env<'env> {
         method: jmethodID,
         args: *const jvalue,
     ) -> Result<jshort, Local<'env, E>> {
+        self.check_ref(this);
         let result = ((**self.env).v1_2.CallShortMethodA)(self.env, this, method, args);
         self.exception_check()?;
         Ok(result)
@@ -437,6 +936,7 @@ impl<'env> Env<'env> {
         method: jmethodID,
         args: *const jvalue,
     ) -> Result<jint, Local<'env, E>> {
+        self.check_ref(this);
         let result = ((**self.env).v1_2.CallIntMethodA)(self.env, this, method, args);
         self.exception_check()?;
         Ok(result)
@@ -448,6 +948,7 @@ impl<'env> Env<'env> {
         method: jmethodID,
         args: *const jvalue,
     ) -> Result<jlong, Local<'env, E>> {
+        self.check_ref(this);
         let result = ((**self.env).v1_2.CallLongMethodA)(self.env, this, method, args);
         self.exception_check()?;
         Ok(result)
@@ -459,6 +960,7 @@ impl<'env> Env<'env> {
         method: jmethodID,
         args: *const jvalue,
     ) -> Result<jfloat, Local<'env, E>> {
+        self.check_ref(this);
         let result = ((**self.env).v1_2.CallFloatMethodA)(self.env, this, method, args);
         self.exception_check()?;
         Ok(result)
@@ -470,6 +972,7 @@ impl<'env> Env<'env> {
         method: jmethodID,
         args: *const jvalue,
     ) -> Result<jdouble, Local<'env, E>> {
+        self.check_ref(this);
         let result = ((**self.env).v1_2.CallDoubleMethodA)(self.env, this, method, args);
         self.exception_check()?;
         Ok(result)
@@ -481,10 +984,146 @@ impl<'env> Env<'env> {
         method: jmethodID,
         args: *const jvalue,
     ) -> Result<(), Local<'env, E>> {
+        self.check_ref(this);
         ((**self.env).v1_2.CallVoidMethodA)(self.env, this, method, args);
         self.exception_check()
     }
 
+    // Non-virtual Instance Methods
+
+    pub unsafe fn call_nonvirtual_object_method_a<R: ReferenceType, E: ThrowableType>(
+        self,
+        this: jobject,
+        class: jclass,
+        method: jmethodID,
+        args: *const jvalue,
+    ) -> Result<Option<Local<'env, R>>, Local<'env, E>> {
+        self.check_ref(this);
+        let result = ((**self.env).v1_2.CallNonvirtualObjectMethodA)(self.env, this, class, method, args);
+        self.exception_check()?;
+        if result.is_null() {
+            Ok(None)
+        } else {
+            Ok(Some(Local::from_raw(self, result)))
+        }
+    }
+
+    pub unsafe fn call_nonvirtual_boolean_method_a<E: ThrowableType>(
+        self,
+        this: jobject,
+        class: jclass,
+        method: jmethodID,
+        args: *const jvalue,
+    ) -> Result<bool, Local<'env, E>> {
+        self.check_ref(this);
+        let result = ((**self.env).v1_2.CallNonvirtualBooleanMethodA)(self.env, this, class, method, args);
+        self.exception_check()?;
+        Ok(result != JNI_FALSE)
+    }
+
+    pub unsafe fn call_nonvirtual_byte_method_a<E: ThrowableType>(
+        self,
+        this: jobject,
+        class: jclass,
+        method: jmethodID,
+        args: *const jvalue,
+    ) -> Result<jbyte, Local<'env, E>> {
+        self.check_ref(this);
+        let result = ((**self.env).v1_2.CallNonvirtualByteMethodA)(self.env, this, class, method, args);
+        self.exception_check()?;
+        Ok(result)
+    }
+
+    pub unsafe fn call_nonvirtual_char_method_a<E: ThrowableType>(
+        self,
+        this: jobject,
+        class: jclass,
+        method: jmethodID,
+        args: *const jvalue,
+    ) -> Result<jchar, Local<'env, E>> {
+        self.check_ref(this);
+        let result = ((**self.env).v1_2.CallNonvirtualCharMethodA)(self.env, this, class, method, args);
+        self.exception_check()?;
+        Ok(result)
+    }
+
+    pub unsafe fn call_nonvirtual_short_method_a<E: ThrowableType>(
+        self,
+        this: jobject,
+        class: jclass,
+        method: jmethodID,
+        args: *const jvalue,
+    ) -> Result<jshort, Local<'env, E>> {
+        self.check_ref(this);
+        let result = ((**self.env).v1_2.CallNonvirtualShortMethodA)(self.env, this, class, method, args);
+        self.exception_check()?;
+        Ok(result)
+    }
+
+    pub unsafe fn call_nonvirtual_int_method_a<E: ThrowableType>(
+        self,
+        this: jobject,
+        class: jclass,
+        method: jmethodID,
+        args: *const jvalue,
+    ) -> Result<jint, Local<'env, E>> {
+        self.check_ref(this);
+        let result = ((**self.env).v1_2.CallNonvirtualIntMethodA)(self.env, this, class, method, args);
+        self.exception_check()?;
+        Ok(result)
+    }
+
+    pub unsafe fn call_nonvirtual_long_method_a<E: ThrowableType>(
+        self,
+        this: jobject,
+        class: jclass,
+        method: jmethodID,
+        args: *const jvalue,
+    ) -> Result<jlong, Local<'env, E>> {
+        self.check_ref(this);
+        let result = ((**self.env).v1_2.CallNonvirtualLongMethodA)(self.env, this, class, method, args);
+        self.exception_check()?;
+        Ok(result)
+    }
+
+    pub unsafe fn call_nonvirtual_float_method_a<E: ThrowableType>(
+        self,
+        this: jobject,
+        class: jclass,
+        method: jmethodID,
+        args: *const jvalue,
+    ) -> Result<jfloat, Local<'env, E>> {
+        self.check_ref(this);
+        let result = ((**self.env).v1_2.CallNonvirtualFloatMethodA)(self.env, this, class, method, args);
+        self.exception_check()?;
+        Ok(result)
+    }
+
+    pub unsafe fn call_nonvirtual_double_method_a<E: ThrowableType>(
+        self,
+        this: jobject,
+        class: jclass,
+        method: jmethodID,
+        args: *const jvalue,
+    ) -> Result<jdouble, Local<'env, E>> {
+        self.check_ref(this);
+        let result = ((**self.env).v1_2.CallNonvirtualDoubleMethodA)(self.env, this, class, method, args);
+        self.exception_check()?;
+        Ok(result)
+    }
+
+    pub unsafe fn call_nonvirtual_void_method_a<E: ThrowableType>(
+        self,
+        this: jobject,
+        class: jclass,
+        method: jmethodID,
+        args: *const jvalue,
+    ) -> Result<(), Local<'env, E>> {
+        self.check_ref(this);
+        ((**self.env).v1_2.CallNonvirtualVoidMethodA)(self.env, this, class, method, args);
+        self.exception_check()
+    }
+
     // Static Methods
 
     pub unsafe fn call_static_object_method_a<R: ReferenceType, E: ThrowableType>(
@@ -493,6 +1132,7 @@ impl<'env> Env<'env> {
         method: jmethodID,
         args: *const jvalue,
     ) -> Result<Option<Local<'env, R>>, Local<'env, E>> {
+        self.check_ref(class);
         let result = ((**self.env).v1_2.CallStaticObjectMethodA)(self.env, class, method, args);
         self.exception_check()?;
         if result.is_null() {
@@ -508,6 +1148,7 @@ impl<'env> Env<'env> {
         method: jmethodID,
         args: *const jvalue,
     ) -> Result<bool, Local<'env, E>> {
+        self.check_ref(class);
         let result = ((**self.env).v1_2.CallStaticBooleanMethodA)(self.env, class, method, args);
         self.exception_check()?;
         Ok(result != JNI_FALSE)
@@ -519,6 +1160,7 @@ impl<'env> Env<'env> {
         method: jmethodID,
         args: *const jvalue,
     ) -> Result<jbyte, Local<'env, E>> {
+        self.check_ref(class);
         let result = ((**self.env).v1_2.CallStaticByteMethodA)(self.env, class, method, args);
         self.exception_check()?;
         Ok(result)
@@ -530,6 +1172,7 @@ impl<'env> Env<'env> {
         method: jmethodID,
         args: *const jvalue,
     ) -> Result<jchar, Local<'env, E>> {
+        self.check_ref(class);
         let result = ((**self.env).v1_2.CallStaticCharMethodA)(self.env, class, method, args);
         self.exception_check()?;
         Ok(result)
@@ -541,6 +1184,7 @@ impl<'env> Env<'env> {
         method: jmethodID,
         args: *const jvalue,
     ) -> Result<jshort, Local<'env, E>> {
+        self.check_ref(class);
         let result = ((**self.env).v1_2.CallStaticShortMethodA)(self.env, class, method, args);
         self.exception_check()?;
         Ok(result)
@@ -552,6 +1196,7 @@ impl<'env> Env<'env> {
         method: jmethodID,
         args: *const jvalue,
     ) -> Result<jint, Local<'env, E>> {
+        self.check_ref(class);
         let result = ((**self.env).v1_2.CallStaticIntMethodA)(self.env, class, method, args);
         self.exception_check()?;
         Ok(result)
@@ -563,6 +1208,7 @@ impl<'env> Env<'env> {
         method: jmethodID,
         args: *const jvalue,
     ) -> Result<jlong, Local<'env, E>> {
+        self.check_ref(class);
         let result = ((**self.env).v1_2.CallStaticLongMethodA)(self.env, class, method, args);
         self.exception_check()?;
         Ok(result)
@@ -574,6 +1220,7 @@ impl<'env> Env<'env> {
         method: jmethodID,
         args: *const jvalue,
     ) -> Result<jfloat, Local<'env, E>> {
+        self.check_ref(class);
         let result = ((**self.env).v1_2.CallStaticFloatMethodA)(self.env, class, method, args);
         self.exception_check()?;
         Ok(result)
@@ -585,6 +1232,7 @@ impl<'env> Env<'env> {
         method: jmethodID,
         args: *const jvalue,
     ) -> Result<jdouble, Local<'env, E>> {
+        self.check_ref(class);
         let result = ((**self.env).v1_2.CallStaticDoubleMethodA)(self.env, class, method, args);
         self.exception_check()?;
         Ok(result)
@@ -596,6 +1244,7 @@ impl<'env> Env<'env> {
         method: jmethodID,
         args: *const jvalue,
     ) -> Result<(), Local<'env, E>> {
+        self.check_ref(class);
         ((**self.env).v1_2.CallStaticVoidMethodA)(self.env, class, method, args);
         self.exception_check()
     }
@@ -603,6 +1252,7 @@ impl<'env> Env<'env> {
     // Instance Fields
 
     pub unsafe fn get_object_field<R: ReferenceType>(self, this: jobject, field: jfieldID) -> Option<Local<'env, R>> {
+        self.check_ref(this);
         let result = ((**self.env).v1_2.GetObjectField)(self.env, this, field);
         if result.is_null() {
             None
@@ -612,71 +1262,88 @@ impl<'env> Env<'env> {
     }
 
     pub unsafe fn get_boolean_field(self, this: jobject, field: jfieldID) -> bool {
+        self.check_ref(this);
         let result = ((**self.env).v1_2.GetBooleanField)(self.env, this, field);
         result != JNI_FALSE
     }
 
     pub unsafe fn get_byte_field(self, this: jobject, field: jfieldID) -> jbyte {
+        self.check_ref(this);
         ((**self.env).v1_2.GetByteField)(self.env, this, field)
     }
 
     pub unsafe fn get_char_field(self, this: jobject, field: jfieldID) -> jchar {
+        self.check_ref(this);
         ((**self.env).v1_2.GetCharField)(self.env, this, field)
     }
 
     pub unsafe fn get_short_field(self, this: jobject, field: jfieldID) -> jshort {
+        self.check_ref(this);
         ((**self.env).v1_2.GetShortField)(self.env, this, field)
     }
 
     pub unsafe fn get_int_field(self, this: jobject, field: jfieldID) -> jint {
+        self.check_ref(this);
         ((**self.env).v1_2.GetIntField)(self.env, this, field)
     }
 
     pub unsafe fn get_long_field(self, this: jobject, field: jfieldID) -> jlong {
+        self.check_ref(this);
         ((**self.env).v1_2.GetLongField)(self.env, this, field)
     }
 
     pub unsafe fn get_float_field(self, this: jobject, field: jfieldID) -> jfloat {
+        self.check_ref(this);
         ((**self.env).v1_2.GetFloatField)(self.env, this, field)
     }
 
     pub unsafe fn get_double_field(self, this: jobject, field: jfieldID) -> jdouble {
+        self.check_ref(this);
         ((**self.env).v1_2.GetDoubleField)(self.env, this, field)
     }
 
     pub unsafe fn set_object_field<R: ReferenceType>(self, this: jobject, field: jfieldID, value: impl AsArg<R>) {
+        self.check_ref(this);
         ((**self.env).v1_2.SetObjectField)(self.env, this, field, value.as_arg());
     }
 
     pub unsafe fn set_boolean_field(self, this: jobject, field: jfieldID, value: bool) {
+        self.check_ref(this);
         ((**self.env).v1_2.SetBooleanField)(self.env, this, field, if value { JNI_TRUE } else { JNI_FALSE });
     }
 
     pub unsafe fn set_byte_field(self, this: jobject, field: jfieldID, value: jbyte) {
+        self.check_ref(this);
         ((**self.env).v1_2.SetByteField)(self.env, this, field, value);
     }
 
     pub unsafe fn set_char_field(self, this: jobject, field: jfieldID, value: jchar) {
+        self.check_ref(this);
         ((**self.env).v1_2.SetCharField)(self.env, this, field, value);
     }
 
     pub unsafe fn set_short_field(self, this: jobject, field: jfieldID, value: jshort) {
+        self.check_ref(this);
         ((**self.env).v1_2.SetShortField)(self.env, this, field, value);
     }
 
     pub unsafe fn set_int_field(self, this: jobject, field: jfieldID, value: jint) {
+        self.check_ref(this);
         ((**self.env).v1_2.SetIntField)(self.env, this, field, value);
     }
 
     pub unsafe fn set_long_field(self, this: jobject, field: jfieldID, value: jlong) {
+        self.check_ref(this);
         ((**self.env).v1_2.SetLongField)(self.env, this, field, value);
     }
 
     pub unsafe fn set_float_field(self, this: jobject, field: jfieldID, value: jfloat) {
+        self.check_ref(this);
         ((**self.env).v1_2.SetFloatField)(self.env, this, field, value);
     }
 
     pub unsafe fn set_double_field(self, this: jobject, field: jfieldID, value: jdouble) {
+        self.check_ref(this);
         ((**self.env).v1_2.SetDoubleField)(self.env, this, field, value);
     }
 
@@ -687,6 +1354,7 @@ impl<'env> Env<'env> {
         class: jclass,
         field: jfieldID,
     ) -> Option<Local<'env, R>> {
+        self.check_ref(class);
         let result = ((**self.env).v1_2.GetStaticObjectField)(self.env, class, field);
         if result.is_null() {
             None
@@ -696,35 +1364,43 @@ impl<'env> Env<'env> {
     }
 
     pub unsafe fn get_static_boolean_field(self, class: jclass, field: jfieldID) -> bool {
+        self.check_ref(class);
         let result = ((**self.env).v1_2.GetStaticBooleanField)(self.env, class, field);
         result != JNI_FALSE
     }
 
     pub unsafe fn get_static_byte_field(self, class: jclass, field: jfieldID) -> jbyte {
+        self.check_ref(class);
         ((**self.env).v1_2.GetStaticByteField)(self.env, class, field)
     }
 
     pub unsafe fn get_static_char_field(self, class: jclass, field: jfieldID) -> jchar {
+        self.check_ref(class);
         ((**self.env).v1_2.GetStaticCharField)(self.env, class, field)
     }
 
     pub unsafe fn get_static_short_field(self, class: jclass, field: jfieldID) -> jshort {
+        self.check_ref(class);
         ((**self.env).v1_2.GetStaticShortField)(self.env, class, field)
     }
 
     pub unsafe fn get_static_int_field(self, class: jclass, field: jfieldID) -> jint {
+        self.check_ref(class);
         ((**self.env).v1_2.GetStaticIntField)(self.env, class, field)
     }
 
     pub unsafe fn get_static_long_field(self, class: jclass, field: jfieldID) -> jlong {
+        self.check_ref(class);
         ((**self.env).v1_2.GetStaticLongField)(self.env, class, field)
     }
 
     pub unsafe fn get_static_float_field(self, class: jclass, field: jfieldID) -> jfloat {
+        self.check_ref(class);
         ((**self.env).v1_2.GetStaticFloatField)(self.env, class, field)
     }
 
     pub unsafe fn get_static_double_field(self, class: jclass, field: jfieldID) -> jdouble {
+        self.check_ref(class);
         ((**self.env).v1_2.GetStaticDoubleField)(self.env, class, field)
     }
 
@@ -734,43 +1410,58 @@ impl<'env> Env<'env> {
         field: jfieldID,
         value: impl AsArg<R>,
     ) {
+        self.check_ref(class);
         ((**self.env).v1_2.SetStaticObjectField)(self.env, class, field, value.as_arg());
     }
 
     pub unsafe fn set_static_boolean_field(self, class: jclass, field: jfieldID, value: bool) {
+        self.check_ref(class);
         ((**self.env).v1_2.SetStaticBooleanField)(self.env, class, field, if value { JNI_TRUE } else { JNI_FALSE });
     }
 
     pub unsafe fn set_static_byte_field(self, class: jclass, field: jfieldID, value: jbyte) {
+        self.check_ref(class);
         ((**self.env).v1_2.SetStaticByteField)(self.env, class, field, value);
     }
 
     pub unsafe fn set_static_char_field(self, class: jclass, field: jfieldID, value: jchar) {
+        self.check_ref(class);
         ((**self.env).v1_2.SetStaticCharField)(self.env, class, field, value);
     }
 
     pub unsafe fn set_static_short_field(self, class: jclass, field: jfieldID, value: jshort) {
+        self.check_ref(class);
         ((**self.env).v1_2.SetStaticShortField)(self.env, class, field, value);
     }
 
     pub unsafe fn set_static_int_field(self, class: jclass, field: jfieldID, value: jint) {
+        self.check_ref(class);
         ((**self.env).v1_2.SetStaticIntField)(self.env, class, field, value);
     }
 
     pub unsafe fn set_static_long_field(self, class: jclass, field: jfieldID, value: jlong) {
+        self.check_ref(class);
         ((**self.env).v1_2.SetStaticLongField)(self.env, class, field, value);
     }
 
     pub unsafe fn set_static_float_field(self, class: jclass, field: jfieldID, value: jfloat) {
+        self.check_ref(class);
         ((**self.env).v1_2.SetStaticFloatField)(self.env, class, field, value);
     }
 
     pub unsafe fn set_static_double_field(self, class: jclass, field: jfieldID, value: jdouble) {
+        self.check_ref(class);
         ((**self.env).v1_2.SetStaticDoubleField)(self.env, class, field, value);
     }
+}
 
-    pub fn throw<T: ReferenceType>(self, throwable: &Ref<T>) {
-        let res = unsafe { ((**self.env).v1_2.Throw)(self.env, throwable.as_raw()) };
-        assert_eq!(res, 0);
+/// Pops a local reference frame pushed by [Env::push_local_frame], even if the closure inside it panics.
+struct LocalFrame {
+    env: *mut JNIEnv,
+}
+
+impl Drop for LocalFrame {
+    fn drop(&mut self) {
+        unsafe { ((**self.env).v1_2.PopLocalFrame)(self.env, null_mut()) };
     }
 }
@@ -0,0 +1,231 @@
+//! Launching a JVM from Rust through the [JNI Invocation API](https://docs.oracle.com/javase/8/docs/technotes/guides/jni/spec/invocation.html).
+
+use std::ffi::{CString, NulError, OsString, c_void};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::ptr::null_mut;
+use std::sync::OnceLock;
+
+use jni_sys::*;
+
+use crate::VM;
+
+/// Keeps the loaded `libjvm` alive for the rest of the process. HotSpot does not support creating a second VM
+/// in the same process after the first one was destroyed, so the library is never unloaded.
+static LIBJVM: OnceLock<libloading::Library> = OnceLock::new();
+
+type CreateJavaVMFn = unsafe extern "system" fn(*mut *mut JavaVM, *mut *mut c_void, *mut c_void) -> jint;
+
+/// Builder returned by [VM::create] to configure and launch an embedded JVM.
+///
+/// ```rust,no_run
+/// # use java_spaghetti::VM;
+/// let vm = VM::create()
+///     .classpath("target/java/classes")
+///     .property("file.encoding", "UTF-8")
+///     .option("-Xmx256m")
+///     .build()
+///     .unwrap();
+/// vm.with_env(|env| {
+///     // ...
+/// });
+/// ```
+#[derive(Clone, Debug)]
+pub struct VMBuilder {
+    lib_path: Option<PathBuf>,
+    version: jint,
+    classpath: Vec<PathBuf>,
+    properties: Vec<(String, String)>,
+    options: Vec<String>,
+    ignore_unrecognized: bool,
+}
+
+impl VM {
+    /// Starts configuring a new JVM to be created in this process. See [VMBuilder].
+    pub fn create() -> VMBuilder {
+        VMBuilder {
+            lib_path: None,
+            version: JNI_VERSION_1_8,
+            classpath: Vec::new(),
+            properties: Vec::new(),
+            options: Vec::new(),
+            ignore_unrecognized: false,
+        }
+    }
+}
+
+impl VMBuilder {
+    /// Loads the JVM shared library from the given path, instead of looking for it under `JAVA_HOME`.
+    pub fn lib_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.lib_path = Some(path.into());
+        self
+    }
+
+    /// Sets the JNI version requested from `JNI_CreateJavaVM`. Defaults to `JNI_VERSION_1_8`.
+    pub fn version(mut self, version: jint) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Appends a directory or jar file to `-Djava.class.path`.
+    pub fn classpath(mut self, path: impl Into<PathBuf>) -> Self {
+        self.classpath.push(path.into());
+        self
+    }
+
+    /// Sets a system property, passed to the JVM as `-D{key}={value}`.
+    pub fn property(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.properties.push((key.into(), value.into()));
+        self
+    }
+
+    /// Adds a raw JVM option string, e.g. `-Xmx256m`, `-Xcheck:jni` or `-verbose:gc`.
+    pub fn option(mut self, option: impl Into<String>) -> Self {
+        self.options.push(option.into());
+        self
+    }
+
+    /// Makes the JVM ignore options it doesn't recognize instead of failing. Defaults to `false`.
+    pub fn ignore_unrecognized(mut self, ignore: bool) -> Self {
+        self.ignore_unrecognized = ignore;
+        self
+    }
+
+    /// Loads the JVM shared library and calls `JNI_CreateJavaVM`.
+    ///
+    /// The current thread becomes attached to the new VM as its main thread. Only one VM can be created per process.
+    pub fn build(self) -> Result<VM, InvocationError> {
+        let options = self.option_strings()?;
+        let mut raw_options: Vec<JavaVMOption> = options
+            .iter()
+            .map(|option| JavaVMOption {
+                optionString: option.as_ptr() as *mut _,
+                extraInfo: null_mut(),
+            })
+            .collect();
+
+        let mut args = JavaVMInitArgs {
+            version: self.version,
+            nOptions: raw_options.len() as jint,
+            options: raw_options.as_mut_ptr(),
+            ignoreUnrecognized: self.ignore_unrecognized,
+        };
+
+        let create_java_vm = self.load_create_java_vm()?;
+
+        let mut vm = null_mut();
+        let mut env = null_mut();
+        let res = unsafe { create_java_vm(&mut vm, &mut env, &mut args as *mut JavaVMInitArgs as *mut c_void) };
+        if res != JNI_OK {
+            return Err(InvocationError::CreateFailed(res));
+        }
+        assert!(!vm.is_null());
+        Ok(unsafe { VM::from_raw(vm) })
+    }
+
+    fn option_strings(&self) -> Result<Vec<CString>, InvocationError> {
+        let mut options = Vec::new();
+        if !self.classpath.is_empty() {
+            let classpath = std::env::join_paths(&self.classpath).map_err(|_| InvocationError::InvalidClasspath)?;
+            let mut option = OsString::from("-Djava.class.path=");
+            option.push(classpath);
+            options.push(CString::new(
+                option.into_string().map_err(|_| InvocationError::InvalidClasspath)?,
+            )?);
+        }
+        for (key, value) in &self.properties {
+            options.push(CString::new(format!("-D{key}={value}"))?);
+        }
+        for option in &self.options {
+            options.push(CString::new(option.as_str())?);
+        }
+        Ok(options)
+    }
+
+    fn load_create_java_vm(&self) -> Result<CreateJavaVMFn, InvocationError> {
+        let lib = match LIBJVM.get() {
+            Some(lib) => lib,
+            None => {
+                let path = match &self.lib_path {
+                    Some(path) => path.clone(),
+                    None => find_libjvm()?,
+                };
+                let lib = unsafe { libloading::Library::new(&path) }.map_err(InvocationError::LoadLibrary)?;
+                LIBJVM.get_or_init(|| lib)
+            }
+        };
+        let symbol =
+            unsafe { lib.get::<CreateJavaVMFn>(b"JNI_CreateJavaVM\0") }.map_err(InvocationError::LoadLibrary)?;
+        Ok(*symbol)
+    }
+}
+
+/// Locations of the JVM shared library relative to `JAVA_HOME`, for JDK 9+ and JDK 8 layouts.
+#[cfg(target_os = "windows")]
+const LIBJVM_PATHS: &[&str] = &["bin/server/jvm.dll", "jre/bin/server/jvm.dll", "bin/client/jvm.dll"];
+#[cfg(target_os = "macos")]
+const LIBJVM_PATHS: &[&str] = &["lib/server/libjvm.dylib", "jre/lib/server/libjvm.dylib"];
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const LIBJVM_PATHS: &[&str] = &[
+    "lib/server/libjvm.so",
+    "jre/lib/server/libjvm.so",
+    "jre/lib/amd64/server/libjvm.so",
+    "jre/lib/aarch64/server/libjvm.so",
+    "lib/client/libjvm.so",
+];
+
+fn find_libjvm() -> Result<PathBuf, InvocationError> {
+    let java_home = std::env::var_os("JAVA_HOME").ok_or(InvocationError::JavaHomeNotSet)?;
+    let java_home = Path::new(&java_home);
+    LIBJVM_PATHS
+        .iter()
+        .map(|path| java_home.join(path))
+        .find(|path| path.is_file())
+        .ok_or_else(|| InvocationError::LibraryNotFound(java_home.to_owned()))
+}
+
+/// Error returned by [VMBuilder::build].
+#[derive(Debug)]
+pub enum InvocationError {
+    /// No library path was given and `JAVA_HOME` is not set.
+    JavaHomeNotSet,
+    /// The JVM shared library could not be found under this `JAVA_HOME`.
+    LibraryNotFound(PathBuf),
+    /// The JVM shared library could not be loaded, or doesn't export `JNI_CreateJavaVM`.
+    LoadLibrary(libloading::Error),
+    /// The classpath contains a separator character or is not valid Unicode.
+    InvalidClasspath,
+    /// An option contains a NUL byte.
+    InvalidOption(NulError),
+    /// `JNI_CreateJavaVM` failed with this error code, e.g. `JNI_EEXIST` if a VM already exists.
+    CreateFailed(jint),
+}
+
+impl From<NulError> for InvocationError {
+    fn from(e: NulError) -> Self {
+        Self::InvalidOption(e)
+    }
+}
+
+impl std::error::Error for InvocationError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::LoadLibrary(e) => Some(e),
+            Self::InvalidOption(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for InvocationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::JavaHomeNotSet => f.write_str("JAVA_HOME is not set"),
+            Self::LibraryNotFound(java_home) => write!(f, "couldn't find the JVM library in {}", java_home.display()),
+            Self::LoadLibrary(e) => write!(f, "couldn't load the JVM library: {e}"),
+            Self::InvalidClasspath => f.write_str("invalid classpath"),
+            Self::InvalidOption(e) => write!(f, "invalid JVM option: {e}"),
+            Self::CreateFailed(code) => write!(f, "JNI_CreateJavaVM returned error: {code}"),
+        }
+    }
+}
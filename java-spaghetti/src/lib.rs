@@ -8,6 +8,7 @@
 
 #![feature(arbitrary_self_types)]
 
+use std::ffi::CString;
 use std::fmt;
 
 /// public jni-sys reexport.
@@ -19,29 +20,50 @@ mod refs {
     mod local;
     mod ref_;
     mod return_;
+    mod weak;
 
     pub use arg::*;
     pub use global::*;
     pub use local::*;
     pub use ref_::*;
     pub use return_::*;
+    pub use weak::*;
 }
 
 mod array;
 mod as_arg;
 mod as_jvalue;
+mod byte_buffer;
+mod checked;
+#[cfg(feature = "debug")]
+pub mod debug;
+mod dynamic;
 mod env;
 mod id_cache;
+#[cfg(feature = "invocation")]
+mod invocation;
 mod jni_type;
+mod mutf8;
+#[cfg(feature = "serde")]
+pub mod serde;
 mod string_chars;
+#[cfg(all(test, feature = "invocation"))]
+mod test_vm;
+#[doc(hidden)]
+pub mod trace;
 mod vm;
 
 pub use array::*;
 pub use as_arg::*;
 pub use as_jvalue::*;
+pub use byte_buffer::*;
+pub use dynamic::*;
 pub use env::*;
 pub use id_cache::*;
+#[cfg(feature = "invocation")]
+pub use invocation::*;
 pub use jni_type::JniType;
+pub use mutf8::*;
 pub use refs::*;
 pub use string_chars::*;
 pub use vm::*;
@@ -57,6 +79,99 @@ impl fmt::Display for CastError {
     }
 }
 
+/// Error returned by the `try_require_*` lookups of [Env] and the generated `try_` bindings, when a class or member
+/// is not available, e.g. an API added in a newer Android version or an optional library. Also returned by
+/// [dynamic access](Env::call_method) when the arguments don't match the descriptor.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum LookupError {
+    /// The class is not found.
+    Class { class: CString },
+    /// The class has no instance method with this name and descriptor.
+    Method {
+        class: String,
+        name: CString,
+        descriptor: CString,
+    },
+    /// The class has no static method with this name and descriptor.
+    StaticMethod {
+        class: String,
+        name: CString,
+        descriptor: CString,
+    },
+    /// The class has no instance field with this name and descriptor.
+    Field {
+        class: String,
+        name: CString,
+        descriptor: CString,
+    },
+    /// The class has no static field with this name and descriptor.
+    StaticField {
+        class: String,
+        name: CString,
+        descriptor: CString,
+    },
+    /// An argument, field value or object of dynamic access doesn't match the type `descriptor`, e.g. `I` or
+    /// `Ljava/lang/String;`, or a method descriptor if the number of arguments is wrong.
+    Mismatch { descriptor: CString, reason: &'static str },
+}
+
+impl std::error::Error for LookupError {}
+impl fmt::Display for LookupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (kind, class, name, descriptor) = match self {
+            Self::Class { class } => return write!(f, "couldn't load class {class:?}"),
+            Self::Mismatch { descriptor, reason } => return write!(f, "{reason} for descriptor {descriptor:?}"),
+            Self::Method {
+                class,
+                name,
+                descriptor,
+            } => ("method", class, name, descriptor),
+            Self::StaticMethod {
+                class,
+                name,
+                descriptor,
+            } => ("static method", class, name, descriptor),
+            Self::Field {
+                class,
+                name,
+                descriptor,
+            } => ("field", class, name, descriptor),
+            Self::StaticField {
+                class,
+                name,
+                descriptor,
+            } => ("static field", class, name, descriptor),
+        };
+        write!(f, "could not find {kind} {name:?} {descriptor:?} on class {class:?}")
+    }
+}
+
+/// Error returned by JNI functions added after JNI 1.2, e.g. [Env::is_virtual_thread], when the VM reports an older
+/// JNI version with `GetVersion` and so doesn't have them in its function table.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct VersionError {
+    /// The JNI version that added the function, e.g. [sys::JNI_VERSION_9].
+    pub required: sys::jint,
+    /// The JNI version reported by the VM.
+    pub actual: sys::jint,
+}
+
+impl std::error::Error for VersionError {}
+impl fmt::Display for VersionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let version = |version: sys::jint| match (version >> 16, version & 0xFFFF) {
+            (major, 0) if major >= 9 => format!("{major}"),
+            (major, minor) => format!("{major}.{minor}"),
+        };
+        write!(
+            f,
+            "JNI {} is required, but the VM only supports JNI {}",
+            version(self.required),
+            version(self.actual)
+        )
+    }
+}
+
 /// A marker type indicating this is a valid exception type that all exceptions thrown by java should be compatible with
 pub trait ThrowableType: ReferenceType {}
 
@@ -80,7 +195,8 @@ pub trait JavaDisplay: ReferenceType {
     fn fmt(self: &Ref<'_, Self>, f: &mut fmt::Formatter<'_>) -> fmt::Result;
 }
 
-/// A trait similar to `Debug`. Currently it is implemented by `Throwable` in generated bindings.
+/// A trait similar to `Debug`. Currently it is implemented by [JObject], and by `Throwable` and `String` in generated
+/// bindings.
 pub trait JavaDebug: ReferenceType {
     fn fmt(self: &Ref<'_, Self>, f: &mut fmt::Formatter<'_>) -> fmt::Result;
 }
@@ -0,0 +1,134 @@
+//! Conversions between Rust UTF-8 and the [Modified UTF-8](https://docs.oracle.com/javase/8/docs/technotes/guides/jni/spec/types.html#modified_utf_8_strings)
+//! used by JNI `NewStringUTF`, `GetStringUTFChars` and `GetStringUTFRegion`.
+//!
+//! Modified UTF-8 differs from UTF-8 in two ways: NUL is encoded with two bytes (`C0 80`), and supplementary
+//! characters are encoded as UTF-16 surrogate pairs, with three bytes per surrogate.
+
+use std::borrow::Cow;
+use std::char;
+
+/// Converts Rust UTF-8 to Modified UTF-8, without a NUL terminator.
+///
+/// Strings without NUL and supplementary characters, including all ASCII strings, are borrowed as they are.
+pub fn to_modified_utf8(s: &str) -> Cow<'_, [u8]> {
+    // NUL is the only byte to be re-encoded below 0xF0; 0xF0 and above only start 4-byte sequences.
+    if !s.bytes().any(|b| b == 0 || b >= 0xF0) {
+        return Cow::Borrowed(s.as_bytes());
+    }
+
+    let mut bytes = Vec::with_capacity(s.len() + 8);
+    for c in s.chars() {
+        match c {
+            '\0' => bytes.extend_from_slice(&[0xC0, 0x80]),
+            c if c.len_utf16() == 2 => {
+                for unit in c.encode_utf16(&mut [0; 2]) {
+                    bytes.extend_from_slice(&[
+                        0xE0 | (*unit >> 12) as u8,
+                        0x80 | (*unit >> 6 & 0x3F) as u8,
+                        0x80 | (*unit & 0x3F) as u8,
+                    ]);
+                }
+            }
+            c => bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+        }
+    }
+    Cow::Owned(bytes)
+}
+
+/// Converts Modified UTF-8 to Rust UTF-8, replacing invalid sequences and unpaired surrogates with
+/// [REPLACEMENT_CHARACTER](char::REPLACEMENT_CHARACTER)s (`'\u{FFFD}'`.)
+///
+/// Strings that are valid UTF-8, i.e. without NUL and supplementary characters, are borrowed as they are.
+pub fn from_modified_utf8(bytes: &[u8]) -> Cow<'_, str> {
+    if let Ok(s) = std::str::from_utf8(bytes) {
+        return Cow::Borrowed(s);
+    }
+
+    let continuation = |i: usize| bytes.get(i).is_some_and(|b| b & 0xC0 == 0x80);
+    let mut units = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        let (unit, len) = match b {
+            0x00..=0x7F => (b as u16, 1),
+            0xC0..=0xDF if continuation(i + 1) => (((b & 0x1F) as u16) << 6 | (bytes[i + 1] & 0x3F) as u16, 2),
+            0xE0..=0xEF if continuation(i + 1) && continuation(i + 2) => (
+                ((b & 0x0F) as u16) << 12 | ((bytes[i + 1] & 0x3F) as u16) << 6 | (bytes[i + 2] & 0x3F) as u16,
+                3,
+            ),
+            _ => (char::REPLACEMENT_CHARACTER as u16, 1),
+        };
+        units.push(unit);
+        i += len;
+    }
+
+    Cow::Owned(
+        char::decode_utf16(units)
+            .map(|r| r.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::*;
+
+    #[test]
+    fn test_ascii_is_borrowed() {
+        assert!(matches!(to_modified_utf8("hello"), Cow::Borrowed(b"hello")));
+        assert!(matches!(from_modified_utf8(b"hello"), Cow::Borrowed("hello")));
+        assert!(matches!(to_modified_utf8("caf\u{e9}"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_nul() {
+        assert_eq!(&*to_modified_utf8("a\0b"), b"a\xC0\x80b");
+        assert_eq!(from_modified_utf8(b"a\xC0\x80b"), "a\0b");
+    }
+
+    #[test]
+    fn test_supplementary() {
+        // U+1F600 is the surrogate pair D83D DE00.
+        let mutf8 = b"x\xED\xA0\xBD\xED\xB8\x80y";
+        assert_eq!(&*to_modified_utf8("x\u{1F600}y"), mutf8);
+        assert_eq!(from_modified_utf8(mutf8), "x\u{1F600}y");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for s in [
+            "",
+            "\0",
+            "\0\0",
+            "a\u{7FF}\u{800}\u{FFFF}",
+            "\u{10000}\u{10FFFF}\0\u{1F600}",
+        ] {
+            let mutf8 = to_modified_utf8(s);
+            assert!(!mutf8.contains(&0));
+            assert_eq!(from_modified_utf8(&mutf8), s);
+        }
+    }
+
+    #[test]
+    fn test_unpaired_surrogates() {
+        // A high surrogate alone, a low surrogate alone, and a low surrogate before a high one.
+        assert_eq!(from_modified_utf8(b"\xED\xA0\xBDa"), "\u{FFFD}a");
+        assert_eq!(from_modified_utf8(b"a\xED\xB8\x80"), "a\u{FFFD}");
+        assert_eq!(from_modified_utf8(b"\xED\xB8\x80\xED\xA0\xBD"), "\u{FFFD}\u{FFFD}");
+    }
+
+    #[test]
+    fn test_malformed() {
+        // A stray continuation byte, truncated sequences, and a 4-byte UTF-8 lead byte.
+        assert_eq!(from_modified_utf8(b"a\x80b"), "a\u{FFFD}b");
+        assert_eq!(from_modified_utf8(b"a\xC3"), "a\u{FFFD}");
+        assert_eq!(from_modified_utf8(b"a\xE2\x82"), "a\u{FFFD}\u{FFFD}");
+        assert_eq!(from_modified_utf8(b"\xE2\x82b"), "\u{FFFD}\u{FFFD}b");
+        assert_eq!(
+            from_modified_utf8(b"\xC0\x80\xF0\x9F\x98\x80"),
+            "\0\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}"
+        );
+    }
+}
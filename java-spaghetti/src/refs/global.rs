@@ -2,7 +2,7 @@ use std::marker::PhantomData;
 
 use jni_sys::*;
 
-use crate::{Env, Local, Ref, ReferenceType, VM};
+use crate::{Env, Local, Ref, ReferenceType, VM, Weak};
 
 /// A [Global](https://www.ibm.com/docs/en/sdk-java-technology/8?topic=collector-overview-jni-object-references),
 /// non-null, reference to a Java object (+ [VM]).
@@ -32,6 +32,8 @@ impl<T: ReferenceType> Global<T> {
     /// `object` must be an owned non-null JNI global reference to an object of type `T`,
     /// not to be deleted by another wrapper.
     pub unsafe fn from_raw(vm: VM, object: jobject) -> Self {
+        #[cfg(feature = "debug")]
+        T::static_with_jni_type(|jni_type| crate::debug::track(object, jni_type));
         Self {
             object,
             vm,
@@ -53,6 +55,8 @@ impl<T: ReferenceType> Global<T> {
     /// one JNI global reference; prevents `DeleteGlobalRef` from being called on dropping.
     pub fn into_raw(self) -> jobject {
         let object = self.object;
+        #[cfg(feature = "debug")]
+        crate::debug::untrack(object);
         std::mem::forget(self); // Don't delete the object.
         object
     }
@@ -70,6 +74,11 @@ impl<T: ReferenceType> Global<T> {
     pub fn as_ref<'env>(&'env self, env: Env<'env>) -> Ref<'env, T> {
         unsafe { Ref::from_raw(env, self.object) }
     }
+
+    /// Returns a new JNI weak global reference of the same Java object.
+    pub fn as_weak(&self) -> Weak<T> {
+        self.vm.with_env(|env| self.as_ref(env).as_weak())
+    }
 }
 
 impl<'env, T: ReferenceType> From<Local<'env, T>> for Global<T> {
@@ -102,17 +111,15 @@ impl<T: ReferenceType> Clone for Global<T> {
             let env = env.as_raw();
             let object = unsafe { ((**env).v1_2.NewGlobalRef)(env, self.object) };
             assert!(!object.is_null());
-            Self {
-                object,
-                vm: self.vm,
-                pd: PhantomData,
-            }
+            unsafe { Self::from_raw(self.vm, object) }
         })
     }
 }
 
 impl<T: ReferenceType> Drop for Global<T> {
     fn drop(&mut self) {
+        #[cfg(feature = "debug")]
+        crate::debug::untrack(self.object);
         self.vm.with_env(|env| {
             let env = env.as_raw();
             unsafe { ((**env).v1_2.DeleteGlobalRef)(env, self.object) }
@@ -4,7 +4,7 @@ use std::ops::Deref;
 
 use jni_sys::*;
 
-use crate::{AssignableTo, Env, Global, JavaDebug, JavaDisplay, Ref, ReferenceType, Return};
+use crate::{AssignableTo, Env, Global, JavaDebug, JavaDisplay, Ref, ReferenceType, Return, Weak};
 
 /// A [Local](https://www.ibm.com/docs/en/sdk-java-technology/8?topic=collector-overview-jni-object-references),
 /// non-null, reference to a Java object (+ [Env]) limited to the current thread/stack.
@@ -81,6 +81,11 @@ impl<'env, T: ReferenceType> Local<'env, T> {
         self.as_ref().as_global()
     }
 
+    /// Returns a new JNI weak global reference of the same Java object.
+    pub fn as_weak(&self) -> Weak<T> {
+        self.as_ref().as_weak()
+    }
+
     /// Creates and leaks a new local reference to be returned from the JNI `extern` callback function.
     /// It will be freed as soon as the control flow returns to Java.
     pub fn as_return(&self) -> Return<'env, T> {
@@ -100,6 +105,17 @@ impl<'env, T: ReferenceType> Local<'env, T> {
         Ok(unsafe { transmute::<Local<'_, T>, Local<'_, U>>(self) })
     }
 
+    /// Tries to cast itself to a JNI reference of type `U`, giving itself back on failure.
+    ///
+    /// Useful for matching a caught exception against several exception types in turn.
+    pub fn downcast<U: ReferenceType>(self) -> Result<Local<'env, U>, Self> {
+        match self.as_ref().check_assignable::<U>() {
+            // Memory layout of the inner `Ref<'env, U>` is the same as `Ref<'env, T>`.
+            Ok(()) => Ok(unsafe { transmute::<Local<'_, T>, Local<'_, U>>(self) }),
+            Err(_) => Err(self),
+        }
+    }
+
     /// Casts itself towards a super class type, without the cost of runtime checking.
     pub fn upcast<U: ReferenceType>(self) -> Local<'env, U>
     where
@@ -5,7 +5,7 @@ use std::ops::Deref;
 
 use jni_sys::jobject;
 
-use crate::{AssignableTo, Env, Global, JavaDebug, JavaDisplay, Local, ReferenceType};
+use crate::{AssignableTo, Env, Global, JavaDebug, JavaDisplay, Local, ReferenceType, Weak};
 
 /// A non-null, [reference](https://www.ibm.com/docs/en/sdk-java-technology/8?topic=collector-overview-jni-object-references)
 /// to a Java object (+ [Env]).  This may refer to a [Local](crate::Local), [Global](crate::Global), local [Arg](crate::Arg), etc.
@@ -36,6 +36,7 @@ impl<'env, T: ReferenceType> Ref<'env, T> {
     ///   This means it should not be a raw pointer managed by [Local] or any other wrapper that deletes it on dropping.
     /// - `object` references an instance of type `T`.
     pub unsafe fn from_raw(env: Env<'env>, object: jobject) -> Self {
+        unsafe { env.check_instance::<T>(object) };
         Self {
             object,
             env,
@@ -62,6 +63,15 @@ impl<'env, T: ReferenceType> Ref<'env, T> {
         unsafe { Global::from_raw(env.vm(), object) }
     }
 
+    /// Returns a new JNI weak global reference of the same Java object.
+    pub fn as_weak(&self) -> Weak<T> {
+        let env = self.env();
+        let jnienv = env.as_raw();
+        let object = unsafe { ((**jnienv).v1_2.NewWeakGlobalRef)(jnienv, self.as_raw()) };
+        assert!(!object.is_null());
+        unsafe { Weak::from_raw(env.vm(), object) }
+    }
+
     /// Returns a new JNI local reference of the same Java object.
     pub fn as_local(&self) -> Local<'env, T> {
         let env = self.env();
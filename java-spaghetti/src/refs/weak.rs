@@ -0,0 +1,147 @@
+use std::marker::PhantomData;
+
+use jni_sys::*;
+
+use crate::{Env, Global, Local, Ref, ReferenceType, VM};
+
+/// A [Weak Global](https://docs.oracle.com/javase/8/docs/technotes/guides/jni/spec/functions.html#weak_global_references),
+/// reference to a Java object (+ [VM]), which does not prevent the object from being garbage collected.
+///
+/// Like [Global], this can be stored statically and shared between threads. It cannot be used directly, instead it
+/// must be upgraded with [Weak::upgrade_local] or [Weak::upgrade_global], which return `None` once the object has
+/// been collected.
+///
+/// **Not FFI Safe:**  `#[repr(rust)]`, and exact layout is likely to change - depending on exact features used - in the
+/// future.
+pub struct Weak<T: ReferenceType> {
+    object: jweak,
+    vm: VM,
+    pd: PhantomData<T>,
+}
+
+unsafe impl<T: ReferenceType> Send for Weak<T> {}
+unsafe impl<T: ReferenceType> Sync for Weak<T> {}
+
+impl<T: ReferenceType> Weak<T> {
+    /// Wraps an owned raw JNI weak global reference, taking the ownership.
+    ///
+    /// # Safety
+    ///
+    /// `object` must be an owned non-null JNI weak global reference to an object of type `T`,
+    /// not to be deleted by another wrapper.
+    pub unsafe fn from_raw(vm: VM, object: jweak) -> Self {
+        Self {
+            object,
+            vm,
+            pd: PhantomData,
+        }
+    }
+
+    /// Gets the [VM] under which the JNI reference is created.
+    pub fn vm(&self) -> VM {
+        self.vm
+    }
+
+    /// Returns the raw JNI reference pointer.
+    pub fn as_raw(&self) -> jweak {
+        self.object
+    }
+
+    /// Leaks the `Weak` and turns it into a raw pointer, preserving the ownership of one JNI weak
+    /// global reference; prevents `DeleteWeakGlobalRef` from being called on dropping.
+    pub fn into_raw(self) -> jweak {
+        let object = self.object;
+        std::mem::forget(self); // Don't delete the object.
+        object
+    }
+
+    /// Returns a new JNI local reference of the Java object, or `None` if it has been garbage collected.
+    pub fn upgrade_local<'env>(&self, env: Env<'env>) -> Option<Local<'env, T>> {
+        let jnienv = env.as_raw();
+        let object = unsafe { ((**jnienv).v1_2.NewLocalRef)(jnienv, self.object) };
+        if object.is_null() {
+            None
+        } else {
+            Some(unsafe { Local::from_raw(env, object) })
+        }
+    }
+
+    /// Returns a new JNI global reference of the Java object, or `None` if it has been garbage collected.
+    pub fn upgrade_global(&self) -> Option<Global<T>> {
+        self.vm.with_env(|env| {
+            let jnienv = env.as_raw();
+            let object = unsafe { ((**jnienv).v1_2.NewGlobalRef)(jnienv, self.object) };
+            if object.is_null() {
+                None
+            } else {
+                Some(unsafe { Global::from_raw(self.vm, object) })
+            }
+        })
+    }
+
+    /// Tests whether the Java object has been garbage collected. Note that the object may still be collected
+    /// right after this returns `false`; use [Weak::upgrade_local] to keep it alive.
+    pub fn is_garbage_collected(&self) -> bool {
+        self.vm.with_env(|env| {
+            let jnienv = env.as_raw();
+            unsafe { ((**jnienv).v1_2.IsSameObject)(jnienv, self.object, std::ptr::null_mut()) }
+        })
+    }
+}
+
+impl<'env, T: ReferenceType> From<Local<'env, T>> for Weak<T> {
+    fn from(x: Local<'env, T>) -> Self {
+        x.as_weak()
+    }
+}
+
+impl<'env, T: ReferenceType> From<Ref<'env, T>> for Weak<T> {
+    fn from(x: Ref<'env, T>) -> Self {
+        x.as_weak()
+    }
+}
+
+impl<'env, T: ReferenceType> From<&Local<'env, T>> for Weak<T> {
+    fn from(x: &Local<'env, T>) -> Self {
+        x.as_weak()
+    }
+}
+
+impl<'env, T: ReferenceType> From<&Ref<'env, T>> for Weak<T> {
+    fn from(x: &Ref<'env, T>) -> Self {
+        x.as_weak()
+    }
+}
+
+impl<T: ReferenceType> From<&Global<T>> for Weak<T> {
+    fn from(x: &Global<T>) -> Self {
+        x.as_weak()
+    }
+}
+
+impl<T: ReferenceType> Clone for Weak<T> {
+    fn clone(&self) -> Self {
+        self.vm.with_env(|env| {
+            let env = env.as_raw();
+            // This is null if the object has already been collected, which is still a valid cleared `Weak`.
+            let object = unsafe { ((**env).v1_2.NewWeakGlobalRef)(env, self.object) };
+            Self {
+                object,
+                vm: self.vm,
+                pd: PhantomData,
+            }
+        })
+    }
+}
+
+impl<T: ReferenceType> Drop for Weak<T> {
+    fn drop(&mut self) {
+        if self.object.is_null() {
+            return;
+        }
+        self.vm.with_env(|env| {
+            let env = env.as_raw();
+            unsafe { ((**env).v1_2.DeleteWeakGlobalRef)(env, self.object) }
+        });
+    }
+}
@@ -0,0 +1,917 @@
+//! Conversions between Rust values and Java objects with [serde](https://serde.rs/), enabled by the `serde` feature.
+//!
+//! Only the runtime's [dynamic access](crate::Env::call_method) is used, so the conversions work regardless of which
+//! classes have generated bindings. Rust values are serialized into the following Java objects:
+//!
+//! | Rust                                          | Java                                                       |
+//! |-----------------------------------------------|------------------------------------------------------------|
+//! | `bool`, `i8`, `i16`, `i32`, `i64`             | `Boolean`, `Byte`, `Short`, `Integer`, `Long`              |
+//! | `u8`, `u16`, `u32`, `u64` (up to `i64::MAX`)  | `Short`, `Integer`, `Long`, `Long`                         |
+//! | `f32`, `f64`                                  | `Float`, `Double`                                          |
+//! | `char`                                        | `Character`, or `String` for supplementary characters     |
+//! | strings, unit variants                        | `String`                                                   |
+//! | bytes (e.g. with `serde_bytes`)               | `byte[]`                                                   |
+//! | `None`, `()`, unit structs                    | `null`                                                     |
+//! | sequences, tuples, tuple structs              | `java.util.ArrayList`                                      |
+//! | maps, structs                                 | `java.util.HashMap`, with field names as `String` keys     |
+//! | newtype, tuple and struct variants            | `java.util.HashMap` with the single entry `variant: value` |
+//!
+//! The [Deserializer] reads these back, and also accepts any `java.util.Map`, `java.util.Collection` and object array.
+//! A `byte[]` is read as bytes, or as a sequence of `u8` for types such as `Vec<u8>`.
+//!
+//! ```rust,no_run
+//! # use java_spaghetti::Env;
+//! # fn example(env: Env) -> Result<(), java_spaghetti::serde::Error> {
+//! let map = java_spaghetti::serde::to_java(env, &[("width", 640), ("height", 480)])?;
+//! let pairs: Vec<(String, i32)> = java_spaghetti::serde::from_java(map.as_deref())?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::ffi::CStr;
+use std::{fmt, slice};
+
+use ::serde::de::value::SeqDeserializer;
+use ::serde::de::{self, DeserializeOwned, DeserializeSeed, IntoDeserializer, Unexpected, Visitor};
+use ::serde::{Serialize, forward_to_deserialize_any, ser};
+use jni_sys::*;
+
+use crate::{
+    ByteArray, Env, JObject, JValue, JniType, Local, LookupError, PrimitiveArray, Ref, ReferenceType, StringChars,
+    ThrowableType,
+};
+
+/// Error returned by [Serializer] and [Deserializer].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Error {
+    /// A custom error of a `Serialize` or `Deserialize` implementation, or a value that can't be converted.
+    Message(String),
+    /// A class or method used for the conversion is not found.
+    Lookup(LookupError),
+    /// A Java exception was thrown during the conversion, described by its `toString()`.
+    Exception(String),
+}
+
+impl std::error::Error for Error {}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Message(message) => f.write_str(message),
+            Self::Lookup(error) => error.fmt(f),
+            Self::Exception(exception) => write!(f, "Java exception: {exception}"),
+        }
+    }
+}
+
+impl From<LookupError> for Error {
+    fn from(error: LookupError) -> Self {
+        Self::Lookup(error)
+    }
+}
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(message: T) -> Self {
+        Self::Message(message.to_string())
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(message: T) -> Self {
+        Self::Message(message.to_string())
+    }
+}
+
+/// Any `java.lang.Throwable`, to catch exceptions of the dynamic calls.
+enum Throwable {}
+
+unsafe impl ReferenceType for Throwable {}
+unsafe impl JniType for Throwable {
+    fn static_with_jni_type<R>(callback: impl FnOnce(&CStr) -> R) -> R {
+        callback(c"java/lang/Throwable")
+    }
+}
+impl ThrowableType for Throwable {}
+
+fn exception(exception: Local<'_, Throwable>) -> Error {
+    match exception.call_method::<Throwable>(c"toString", c"()Ljava/lang/String;", &[]) {
+        Ok(Ok(JValue::Object(Some(message)))) => Error::Exception(read_string(&message)),
+        _ => Error::Exception("<unknown>".to_owned()),
+    }
+}
+
+fn call<'env>(
+    this: &Ref<'env, JObject>,
+    class: &CStr,
+    name: &CStr,
+    descriptor: &CStr,
+    args: &[JValue<'env>],
+) -> Result<JValue<'env>, Error> {
+    this.env()
+        .call_method::<Throwable>(this, class, name, descriptor, args)?
+        .map_err(exception)
+}
+
+fn call_static<'env>(
+    env: Env<'env>,
+    class: &CStr,
+    name: &CStr,
+    descriptor: &CStr,
+    args: &[JValue<'env>],
+) -> Result<JValue<'env>, Error> {
+    env.call_static_method::<Throwable>(class, name, descriptor, args)?
+        .map_err(exception)
+}
+
+fn new_object<'env>(env: Env<'env>, class: &CStr) -> Result<Local<'env, JObject>, Error> {
+    env.new_object::<Throwable>(class, c"()V", &[])?.map_err(exception)
+}
+
+/// Returns the object returned by `method`, or an error if it's `null`.
+fn non_null<'env>(value: JValue<'env>, method: &str) -> Result<Local<'env, JObject>, Error> {
+    value
+        .into_object()
+        .ok_or_else(|| Error::Message(format!("{method} returned null")))
+}
+
+fn is_instance(object: &Ref<'_, JObject>, class: &CStr) -> Result<bool, Error> {
+    let env = object.env();
+    let class = env.dynamic_class(class)?;
+    Ok(unsafe { ((**env.as_raw()).v1_2.IsInstanceOf)(env.as_raw(), object.as_raw(), class) })
+}
+
+fn new_string<'env>(env: Env<'env>, string: &str) -> Local<'env, JObject> {
+    unsafe { Local::from_raw(env, env.new_string_from_str(string)) }
+}
+
+/// `string` is a `java.lang.String`. Unpaired surrogates are replaced.
+fn read_string(string: &Ref<'_, JObject>) -> String {
+    let (env, string) = (string.env(), string.as_raw());
+    unsafe {
+        env.get_string_ascii(string)
+            .unwrap_or_else(|| StringChars::from_env_jstring(env, string).to_string_lossy())
+    }
+}
+
+/// Serializes `value` into a Java object, see the [module](self) docs. `None` is `null`.
+pub fn to_java<'env, T: Serialize + ?Sized>(env: Env<'env>, value: &T) -> Result<Option<Local<'env, JObject>>, Error> {
+    value.serialize(Serializer::new(env))
+}
+
+/// Deserializes a `T` from a Java object or `null`, see the [module](self) docs.
+pub fn from_java<T: DeserializeOwned>(object: Option<&Ref<'_, impl ReferenceType>>) -> Result<T, Error> {
+    // Every reference type is assignable to `java.lang.Object`.
+    let object = object.map(|object| unsafe { Local::from_raw(object.env(), object.as_local().into_raw()) });
+    T::deserialize(Deserializer::new(object))
+}
+
+/// A [serde::Serializer](::serde::Serializer) creating Java objects, see the [module](self) docs.
+#[derive(Clone, Copy)]
+pub struct Serializer<'env> {
+    env: Env<'env>,
+}
+
+impl<'env> Serializer<'env> {
+    pub fn new(env: Env<'env>) -> Self {
+        Self { env }
+    }
+
+    /// Boxes a primitive with the `valueOf` method of `class`, e.g. `Integer.valueOf(int)`.
+    fn boxed(
+        self,
+        class: &CStr,
+        descriptor: &CStr,
+        value: JValue<'env>,
+    ) -> Result<Option<Local<'env, JObject>>, Error> {
+        Ok(call_static(self.env, class, c"valueOf", descriptor, &[value])?.into_object())
+    }
+
+    /// Wraps the value of a newtype, tuple or struct variant into a map with a single entry.
+    fn variant(
+        self,
+        variant: &str,
+        value: Option<Local<'env, JObject>>,
+    ) -> Result<Option<Local<'env, JObject>>, Error> {
+        let map = new_object(self.env, c"java/util/HashMap")?;
+        put(&map, Some(new_string(self.env, variant)), value)?;
+        Ok(Some(map))
+    }
+
+    fn list(self, variant: Option<&'static str>) -> Result<SerializeList<'env>, Error> {
+        Ok(SerializeList {
+            serializer: self,
+            list: new_object(self.env, c"java/util/ArrayList")?,
+            variant,
+        })
+    }
+
+    fn map(self, variant: Option<&'static str>) -> Result<SerializeMap<'env>, Error> {
+        Ok(SerializeMap {
+            serializer: self,
+            map: new_object(self.env, c"java/util/HashMap")?,
+            key: None,
+            variant,
+        })
+    }
+}
+
+fn put<'env>(
+    map: &Ref<'env, JObject>,
+    key: Option<Local<'env, JObject>>,
+    value: Option<Local<'env, JObject>>,
+) -> Result<(), Error> {
+    let descriptor = c"(Ljava/lang/Object;Ljava/lang/Object;)Ljava/lang/Object;";
+    call(map, c"java/util/Map", c"put", descriptor, &[key.into(), value.into()])?;
+    Ok(())
+}
+
+impl<'env> ser::Serializer for Serializer<'env> {
+    type Ok = Option<Local<'env, JObject>>;
+    type Error = Error;
+
+    type SerializeSeq = SerializeList<'env>;
+    type SerializeTuple = SerializeList<'env>;
+    type SerializeTupleStruct = SerializeList<'env>;
+    type SerializeTupleVariant = SerializeList<'env>;
+    type SerializeMap = SerializeMap<'env>;
+    type SerializeStruct = SerializeMap<'env>;
+    type SerializeStructVariant = SerializeMap<'env>;
+
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok, Error> {
+        self.boxed(c"java/lang/Boolean", c"(Z)Ljava/lang/Boolean;", v.into())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok, Error> {
+        self.boxed(c"java/lang/Byte", c"(B)Ljava/lang/Byte;", v.into())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok, Error> {
+        self.boxed(c"java/lang/Short", c"(S)Ljava/lang/Short;", v.into())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok, Error> {
+        self.boxed(c"java/lang/Integer", c"(I)Ljava/lang/Integer;", v.into())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok, Error> {
+        self.boxed(c"java/lang/Long", c"(J)Ljava/lang/Long;", v.into())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok, Error> {
+        self.serialize_i16(v.into())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok, Error> {
+        self.serialize_i32(v.into())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok, Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok, Error> {
+        let v = i64::try_from(v).map_err(|_| Error::Message(format!("{v} is out of range for java.lang.Long")))?;
+        self.serialize_i64(v)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok, Error> {
+        self.boxed(c"java/lang/Float", c"(F)Ljava/lang/Float;", v.into())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Self::Ok, Error> {
+        self.boxed(c"java/lang/Double", c"(D)Ljava/lang/Double;", v.into())
+    }
+
+    fn serialize_char(self, v: char) -> Result<Self::Ok, Error> {
+        match v.encode_utf16(&mut [0; 2]) {
+            [c] => self.boxed(c"java/lang/Character", c"(C)Ljava/lang/Character;", JValue::Char(*c)),
+            _ => self.serialize_str(v.encode_utf8(&mut [0; 4])),
+        }
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok, Error> {
+        Ok(Some(new_string(self.env, v)))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Error> {
+        let bytes = unsafe { slice::from_raw_parts(v.as_ptr() as *const jbyte, v.len()) };
+        let array = ByteArray::new_from(self.env, bytes);
+        Ok(Some(unsafe { Local::from_raw(self.env, array.into_raw()) }))
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Error> {
+        Ok(None)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Self::Ok, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Error> {
+        Ok(None)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Error> {
+        Ok(None)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok, Error> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Error> {
+        self.variant(variant, value.serialize(self)?)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Error> {
+        self.list(None)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Error> {
+        self.list(None)
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct, Error> {
+        self.list(None)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        self.list(Some(variant))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Error> {
+        self.map(None)
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct, Error> {
+        self.map(None)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        self.map(Some(variant))
+    }
+}
+
+/// Serializes sequences, tuples and tuple variants into a `java.util.ArrayList`.
+pub struct SerializeList<'env> {
+    serializer: Serializer<'env>,
+    list: Local<'env, JObject>,
+    variant: Option<&'static str>,
+}
+
+impl<'env> SerializeList<'env> {
+    fn add<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let value = value.serialize(self.serializer)?;
+        call(
+            &self.list,
+            c"java/util/List",
+            c"add",
+            c"(Ljava/lang/Object;)Z",
+            &[value.into()],
+        )?;
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Option<Local<'env, JObject>>, Error> {
+        match self.variant {
+            Some(variant) => self.serializer.variant(variant, Some(self.list)),
+            None => Ok(Some(self.list)),
+        }
+    }
+}
+
+impl<'env> ser::SerializeSeq for SerializeList<'env> {
+    type Ok = Option<Local<'env, JObject>>;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.add(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Error> {
+        self.finish()
+    }
+}
+
+impl<'env> ser::SerializeTuple for SerializeList<'env> {
+    type Ok = Option<Local<'env, JObject>>;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.add(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Error> {
+        self.finish()
+    }
+}
+
+impl<'env> ser::SerializeTupleStruct for SerializeList<'env> {
+    type Ok = Option<Local<'env, JObject>>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.add(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Error> {
+        self.finish()
+    }
+}
+
+impl<'env> ser::SerializeTupleVariant for SerializeList<'env> {
+    type Ok = Option<Local<'env, JObject>>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.add(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Error> {
+        self.finish()
+    }
+}
+
+/// Serializes maps, structs and struct variants into a `java.util.HashMap`.
+pub struct SerializeMap<'env> {
+    serializer: Serializer<'env>,
+    map: Local<'env, JObject>,
+    /// The key of [ser::SerializeMap::serialize_key], waiting for its value.
+    key: Option<Option<Local<'env, JObject>>>,
+    variant: Option<&'static str>,
+}
+
+impl<'env> SerializeMap<'env> {
+    fn finish(self) -> Result<Option<Local<'env, JObject>>, Error> {
+        match self.variant {
+            Some(variant) => self.serializer.variant(variant, Some(self.map)),
+            None => Ok(Some(self.map)),
+        }
+    }
+}
+
+impl<'env> ser::SerializeMap for SerializeMap<'env> {
+    type Ok = Option<Local<'env, JObject>>;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        self.key = Some(key.serialize(self.serializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let key = self.key.take().expect("serialize_value called before serialize_key");
+        put(&self.map, key, value.serialize(self.serializer)?)
+    }
+
+    fn end(self) -> Result<Self::Ok, Error> {
+        self.finish()
+    }
+}
+
+impl<'env> ser::SerializeStruct for SerializeMap<'env> {
+    type Ok = Option<Local<'env, JObject>>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), Error> {
+        let key = new_string(self.serializer.env, key);
+        put(&self.map, Some(key), value.serialize(self.serializer)?)
+    }
+
+    fn end(self) -> Result<Self::Ok, Error> {
+        self.finish()
+    }
+}
+
+impl<'env> ser::SerializeStructVariant for SerializeMap<'env> {
+    type Ok = Option<Local<'env, JObject>>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), Error> {
+        ser::SerializeStruct::serialize_field(self, key, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Error> {
+        self.finish()
+    }
+}
+
+/// Boxed primitives, with the method to unbox them.
+const BOXES: [(&CStr, &CStr, &CStr); 8] = [
+    (c"java/lang/Boolean", c"booleanValue", c"()Z"),
+    (c"java/lang/Byte", c"byteValue", c"()B"),
+    (c"java/lang/Character", c"charValue", c"()C"),
+    (c"java/lang/Short", c"shortValue", c"()S"),
+    (c"java/lang/Integer", c"intValue", c"()I"),
+    (c"java/lang/Long", c"longValue", c"()J"),
+    (c"java/lang/Float", c"floatValue", c"()F"),
+    (c"java/lang/Double", c"doubleValue", c"()D"),
+];
+
+/// A [serde::Deserializer](::serde::Deserializer) reading a Java object or `null`, see the [module](self) docs.
+pub struct Deserializer<'env> {
+    object: Option<Local<'env, JObject>>,
+}
+
+impl<'env> Deserializer<'env> {
+    pub fn new(object: Option<Local<'env, JObject>>) -> Self {
+        Self { object }
+    }
+}
+
+/// `object` is a `byte[]`.
+fn read_bytes(object: &Ref<'_, JObject>) -> Vec<u8> {
+    let array = unsafe { Local::<ByteArray>::from_raw(object.env(), object.as_local().into_raw()) };
+    array.get_region_as_vec(..).into_iter().map(|b| b as u8).collect()
+}
+
+fn class_name(object: &Ref<'_, JObject>) -> Result<String, Error> {
+    let class = call(object, c"java/lang/Object", c"getClass", c"()Ljava/lang/Class;", &[])?;
+    let class = non_null(class, "Object.getClass()")?;
+    let name = call(&class, c"java/lang/Class", c"getName", c"()Ljava/lang/String;", &[])?;
+    let name = non_null(name, "Class.getName()")?;
+    Ok(read_string(&name))
+}
+
+impl<'de, 'env> de::Deserializer<'de> for Deserializer<'env> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let Some(object) = self.object else {
+            return visitor.visit_unit();
+        };
+
+        if is_instance(&object, c"java/lang/String")? {
+            return visitor.visit_string(read_string(&object));
+        }
+        for (class, method, descriptor) in BOXES {
+            if !is_instance(&object, class)? {
+                continue;
+            }
+            return match call(&object, class, method, descriptor, &[])? {
+                JValue::Boolean(z) => visitor.visit_bool(z),
+                JValue::Byte(b) => visitor.visit_i8(b),
+                JValue::Char(c) => match char::from_u32(c.into()) {
+                    Some(c) => visitor.visit_char(c),
+                    None => Err(de::Error::invalid_value(
+                        Unexpected::Other("unpaired surrogate"),
+                        &visitor,
+                    )),
+                },
+                JValue::Short(s) => visitor.visit_i16(s),
+                JValue::Int(i) => visitor.visit_i32(i),
+                JValue::Long(j) => visitor.visit_i64(j),
+                JValue::Float(f) => visitor.visit_f32(f),
+                JValue::Double(d) => visitor.visit_f64(d),
+                _ => unreachable!(),
+            };
+        }
+        if is_instance(&object, c"[B")? {
+            return visitor.visit_byte_buf(read_bytes(&object));
+        }
+        if is_instance(&object, c"java/util/Map")? {
+            let entries = call(&object, c"java/util/Map", c"entrySet", c"()Ljava/util/Set;", &[])?;
+            let entries = non_null(entries, "Map.entrySet()")?;
+            return visitor.visit_map(MapAccess {
+                entries: Iter::new(&entries)?,
+                value: None,
+            });
+        }
+        if is_instance(&object, c"[Ljava/lang/Object;")? {
+            let descriptor = c"([Ljava/lang/Object;)Ljava/util/List;";
+            let list = call_static(
+                object.env(),
+                c"java/util/Arrays",
+                c"asList",
+                descriptor,
+                &[object.into()],
+            )?;
+            let list = non_null(list, "Arrays.asList()")?;
+            return visitor.visit_seq(Iter::new(&list)?);
+        }
+        if is_instance(&object, c"java/util/Collection")? {
+            return visitor.visit_seq(Iter::new(&object)?);
+        }
+        Err(Error::Message(format!(
+            "unsupported Java class {}",
+            class_name(&object)?
+        )))
+    }
+
+    /// Also reads a `byte[]` as a sequence of `u8`, e.g. into a `Vec<u8>`.
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match &self.object {
+            Some(object) if is_instance(object, c"[B")? => {
+                SeqDeserializer::<_, Error>::new(read_bytes(object).into_iter()).deserialize_any(visitor)
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.object {
+            Some(_) => visitor.visit_some(self),
+            None => visitor.visit_none(),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let Some(object) = &self.object else {
+            return Err(de::Error::invalid_type(Unexpected::Unit, &visitor));
+        };
+        if is_instance(object, c"java/lang/String")? {
+            return visitor.visit_enum(read_string(object).into_deserializer());
+        }
+        if is_instance(object, c"java/util/Map")? {
+            let entries = call(object, c"java/util/Map", c"entrySet", c"()Ljava/util/Set;", &[])?;
+            let entries = non_null(entries, "Map.entrySet()")?;
+            let mut entries = MapAccess {
+                entries: Iter::new(&entries)?,
+                value: None,
+            };
+            if let Some(variant) = entries.next_entry()?
+                && entries.next_entry()?.is_none()
+            {
+                return visitor.visit_enum(EnumAccess {
+                    variant,
+                    value: entries.value.take().unwrap(),
+                });
+            }
+        }
+        Err(Error::Message(
+            "expected a String or a Map with a single entry for an enum".to_owned(),
+        ))
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct tuple_struct map struct identifier ignored_any
+    }
+}
+
+/// Iterates over a `java.lang.Iterable` as a sequence.
+struct Iter<'env> {
+    iterator: Local<'env, JObject>,
+}
+
+impl<'env> Iter<'env> {
+    fn new(iterable: &Ref<'env, JObject>) -> Result<Self, Error> {
+        let iterator = call(
+            iterable,
+            c"java/lang/Iterable",
+            c"iterator",
+            c"()Ljava/util/Iterator;",
+            &[],
+        )?;
+        Ok(Self {
+            iterator: non_null(iterator, "Iterable.iterator()")?,
+        })
+    }
+
+    /// Returns the next element, `Some(None)` being `null`.
+    fn next(&mut self) -> Result<Option<Option<Local<'env, JObject>>>, Error> {
+        let has_next = call(&self.iterator, c"java/util/Iterator", c"hasNext", c"()Z", &[])?;
+        if has_next.as_bool() != Some(true) {
+            return Ok(None);
+        }
+        let next = call(
+            &self.iterator,
+            c"java/util/Iterator",
+            c"next",
+            c"()Ljava/lang/Object;",
+            &[],
+        )?;
+        Ok(Some(next.into_object()))
+    }
+}
+
+impl<'de, 'env> de::SeqAccess<'de> for Iter<'env> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, Error> {
+        match self.next()? {
+            Some(element) => seed.deserialize(Deserializer::new(element)).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Iterates over the `entrySet()` of a `java.util.Map`.
+struct MapAccess<'env> {
+    entries: Iter<'env>,
+    /// The value of the entry whose key was last returned.
+    value: Option<Option<Local<'env, JObject>>>,
+}
+
+impl<'env> MapAccess<'env> {
+    /// Returns the key of the next entry, and keeps its value.
+    fn next_entry(&mut self) -> Result<Option<Option<Local<'env, JObject>>>, Error> {
+        let Some(entry) = self.entries.next()? else {
+            return Ok(None);
+        };
+        let entry = entry.ok_or_else(|| Error::Message("null entry in Map.entrySet()".to_owned()))?;
+        let key = call(&entry, c"java/util/Map$Entry", c"getKey", c"()Ljava/lang/Object;", &[])?;
+        let value = call(
+            &entry,
+            c"java/util/Map$Entry",
+            c"getValue",
+            c"()Ljava/lang/Object;",
+            &[],
+        )?;
+        self.value = Some(value.into_object());
+        Ok(Some(key.into_object()))
+    }
+}
+
+impl<'de, 'env> de::MapAccess<'de> for MapAccess<'env> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, Error> {
+        match self.next_entry()? {
+            Some(key) => seed.deserialize(Deserializer::new(key)).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        let value = self.value.take().expect("next_value_seed called before next_key_seed");
+        seed.deserialize(Deserializer::new(value))
+    }
+}
+
+/// A newtype, tuple or struct variant: the single entry of a `java.util.Map`.
+struct EnumAccess<'env> {
+    variant: Option<Local<'env, JObject>>,
+    value: Option<Local<'env, JObject>>,
+}
+
+impl<'de, 'env> de::EnumAccess<'de> for EnumAccess<'env> {
+    type Error = Error;
+    type Variant = Deserializer<'env>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self::Variant), Error> {
+        let variant = seed.deserialize(Deserializer::new(self.variant))?;
+        Ok((variant, Deserializer::new(self.value)))
+    }
+}
+
+impl<'de, 'env> de::VariantAccess<'de> for Deserializer<'env> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        de::Deserialize::deserialize(self)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_seq(self, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_map(self, visitor)
+    }
+}
+
+#[cfg(all(test, feature = "invocation"))]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde_derive::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::test_vm::with_env;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Shape {
+        Point,
+        Circle(f64),
+        Rect { w: u32, h: u32 },
+        Pair(i8, char),
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Config {
+        name: String,
+        tags: Vec<String>,
+        max: Option<u64>,
+        min: Option<i16>,
+        shapes: Vec<Shape>,
+        extra: BTreeMap<String, bool>,
+        bytes: Vec<u8>,
+        unit: (),
+        emoji: char,
+        pair: (f32, i64),
+    }
+
+    fn round_trip<T: Serialize + DeserializeOwned>(env: Env<'_>, value: &T) -> T {
+        let object = to_java(env, value).unwrap();
+        from_java(object.as_deref()).unwrap()
+    }
+
+    #[test]
+    #[ignore = "needs a JVM, see `test_vm`"]
+    fn test_round_trip() {
+        with_env(|env| {
+            let config = Config {
+                name: "h\u{e9}llo \u{1D11E}\0".to_owned(),
+                tags: vec!["a".to_owned(), String::new()],
+                max: Some(1 << 40),
+                min: None,
+                shapes: vec![
+                    Shape::Point,
+                    Shape::Circle(1.5),
+                    Shape::Rect { w: 3, h: 4 },
+                    Shape::Pair(-1, 'x'),
+                ],
+                extra: BTreeMap::from([("k".to_owned(), true)]),
+                bytes: vec![0, 127, 128, 255],
+                unit: (),
+                emoji: '\u{1F600}',
+                pair: (0.5, i64::MIN),
+            };
+            assert_eq!(round_trip(env, &config), config);
+            assert_eq!(round_trip(env, &Some(3u8)), Some(3u8));
+            assert_eq!(round_trip(env, &None::<u8>), None);
+        });
+    }
+
+    #[test]
+    #[ignore = "needs a JVM, see `test_vm`"]
+    fn test_byte_array() {
+        with_env(|env| {
+            let array = ByteArray::new_from(env, &[1, -2, 3]);
+            let array = unsafe { Local::<JObject>::from_raw(env, array.into_raw()) };
+            assert_eq!(from_java::<Vec<u8>>(Some(&array)), Ok(vec![1, 254, 3]));
+            assert_eq!(from_java::<[u8; 3]>(Some(&array)), Ok([1, 254, 3]));
+            assert_eq!(from_java::<(u8, u8, u8)>(Some(&array)), Ok((1, 254, 3)));
+            assert!(from_java::<[u8; 2]>(Some(&array)).is_err());
+        });
+    }
+
+    #[test]
+    #[ignore = "needs a JVM, see `test_vm`"]
+    fn test_errors() {
+        with_env(|env| {
+            let object = new_object(env, c"java/lang/Object").unwrap();
+            let error = from_java::<u32>(Some(&object)).unwrap_err();
+            assert_eq!(
+                error,
+                Error::Message("unsupported Java class java.lang.Object".to_owned())
+            );
+
+            assert!(matches!(
+                from_java::<u32>(None::<&Ref<JObject>>),
+                Err(Error::Message(_))
+            ));
+
+            let too_big = to_java(env, &u64::MAX).unwrap_err();
+            assert!(matches!(too_big, Error::Message(_)));
+        });
+    }
+}
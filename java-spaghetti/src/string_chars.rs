@@ -1,8 +1,10 @@
+use std::borrow::Cow;
+use std::ffi::{CStr, c_char};
 use std::{char, iter, slice};
 
 use jni_sys::*;
 
-use crate::Env;
+use crate::{Env, from_modified_utf8};
 
 /// Represents a JNI `GetStringChars` + `GetStringLength` query.
 /// It will call `ReleaseStringChars` automatically when dropped.
@@ -64,3 +66,45 @@ impl<'env> Drop for StringChars<'env> {
         unsafe { self.env.release_string_chars(self.string, self.chars) };
     }
 }
+
+/// Represents a JNI `GetStringUTFChars` query.
+/// It will call `ReleaseStringUTFChars` automatically when dropped.
+pub struct StringUtfChars<'env> {
+    env: Env<'env>,
+    string: jstring,
+    chars: *const c_char,
+}
+
+impl<'env> StringUtfChars<'env> {
+    /// Construct a `StringUtfChars` from an [Env] + [jstring].
+    ///
+    /// # Safety
+    ///
+    /// The Java string object referenced by `string` must remain available before the created
+    /// `StringUtfChars` is dropped. This should be true if the JNI reference `string` is not deleted.
+    pub unsafe fn from_env_jstring(env: Env<'env>, string: jstring) -> Self {
+        debug_assert!(!string.is_null());
+
+        let chars = unsafe { env.get_string_utf_chars(string) };
+        assert!(!chars.is_null()); // Only sane failure here is an OOM exception
+
+        Self { env, string, chars }
+    }
+
+    /// Get the [Modified UTF-8](crate::to_modified_utf8) bytes, without the NUL terminator.
+    pub fn bytes(&self) -> &[u8] {
+        unsafe { CStr::from_ptr(self.chars) }.to_bytes()
+    }
+
+    /// Converts the string to Rust UTF-8 with [from_modified_utf8], borrowing it if it has no NUL and supplementary
+    /// characters.
+    pub fn to_str(&self) -> Cow<'_, str> {
+        from_modified_utf8(self.bytes())
+    }
+}
+
+impl<'env> Drop for StringUtfChars<'env> {
+    fn drop(&mut self) {
+        unsafe { self.env.release_string_utf_chars(self.string, self.chars) };
+    }
+}
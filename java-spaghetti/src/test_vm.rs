@@ -0,0 +1,29 @@
+//! The JVM of the tests that need one, created from `JAVA_HOME` with the `invocation` feature.
+//!
+//! These tests are `#[ignore]`d, as they can't pass without a JDK. Run them with `JAVA_HOME` set and
+//! `cargo test --all-features -- --ignored`.
+
+use std::sync::OnceLock;
+
+use crate::{Env, VM};
+
+static TEST_VM: OnceLock<VM> = OnceLock::new();
+
+/// Runs `f` with an [Env] of the test JVM, which is created on first use with `-Xcheck:jni`.
+///
+/// # Panics
+///
+/// If the JVM can't be created, e.g. because `JAVA_HOME` is not set.
+pub(crate) fn with_env(f: impl for<'env> FnOnce(Env<'env>)) {
+    let vm = TEST_VM.get_or_init(|| {
+        // Created on a thread of its own, which then detaches, as the threads of tests end while attached otherwise.
+        let vm = std::thread::spawn(|| {
+            let vm = VM::create().option("-Xcheck:jni").build().unwrap();
+            let raw = vm.as_raw();
+            unsafe { ((**raw).v1_2.DetachCurrentThread)(raw) };
+            vm
+        });
+        vm.join().unwrap()
+    });
+    vm.with_env(f)
+}
@@ -0,0 +1,28 @@
+//! Glue for the `tracing` feature, used by generated bindings.
+//!
+//! Every generated method, constructor and field accessor enters a `jni` span at `DEBUG` level, with the Java
+//! `class`, `member` and `descriptor` as fields. The lookups of [Env](crate::Env) run in nested `jni_lookup` spans,
+//! and Java exceptions taken by [Env::exception_take](crate::Env::exception_take) are recorded as events in the
+//! current span.
+//!
+//! The generated bindings call [enter] whether the feature is enabled or not: a `cfg(feature = "tracing")` in
+//! them would test the features of the crate the bindings are compiled in, not those of `java-spaghetti`. This
+//! relies on [enter] being free without the feature: [MemberSpan] is then a zero-sized type without `Drop`, and
+//! [enter] is inlined and ignores its `&'static str` arguments, so optimized builds keep neither the call nor the
+//! strings. Unoptimized builds may keep an empty call.
+
+/// The entered span of a generated binding, exited when dropped.
+pub struct MemberSpan {
+    #[cfg(feature = "tracing")]
+    _span: tracing::span::EnteredSpan,
+}
+
+/// Enters the span of a generated binding for the given Java member, e.g. `("java/lang/String", "length", "()I")`.
+#[inline(always)]
+#[allow(unused_variables)]
+pub fn enter(class: &'static str, member: &'static str, descriptor: &'static str) -> MemberSpan {
+    MemberSpan {
+        #[cfg(feature = "tracing")]
+        _span: tracing::debug_span!("jni", class, member, descriptor).entered(),
+    }
+}
@@ -1,9 +1,11 @@
 use std::cell::{Cell, OnceCell};
+use std::ffi::{CString, c_void};
+use std::marker::PhantomData;
 use std::ptr::null_mut;
 
 use jni_sys::*;
 
-use crate::Env;
+use crate::{Env, Global, ReferenceType, to_modified_utf8};
 
 /// FFI: Use **&VM** instead of `*const JavaVM`.  This represents a global, process-wide Java exection environment.
 ///
@@ -39,28 +41,39 @@ impl VM {
         Self(vm)
     }
 
+    /// Calls `callback` with the [Env] of the current thread, attaching the thread to the VM if needed.
+    ///
+    /// A thread attached here stays attached until it exits. Use [VM::with_env_options] to attach it as a daemon
+    /// or with a name, or [VM::attach_current_thread] to detach it earlier.
     pub fn with_env<F, R>(&self, callback: F) -> R
     where
         F: for<'env> FnOnce(Env<'env>) -> R,
     {
-        let mut env = null_mut();
-        let just_attached = match unsafe { ((**self.0).v1_2.GetEnv)(self.0, &mut env, JNI_VERSION_1_2) } {
-            JNI_OK => false,
-            JNI_EDETACHED => {
-                let ret = unsafe { ((**self.0).v1_2.AttachCurrentThread)(self.0, &mut env, null_mut()) };
-                if ret != JNI_OK {
-                    panic!("AttachCurrentThread returned unknown error: {ret}")
-                }
+        self.with_env_options(&AttachOptions::new(), callback)
+    }
+
+    /// Like [VM::with_env], but attaches the current thread with `options` if it is not attached yet.
+    pub fn with_env_options<F, R>(&self, options: &AttachOptions, callback: F) -> R
+    where
+        F: for<'env> FnOnce(Env<'env>) -> R,
+    {
+        let (env, just_attached) = match self.get_env(options.version) {
+            Ok(Some(env)) => (env, false),
+            Err(JNI_EVERSION) => panic!("GetEnv returned JNI_EVERSION"),
+            Err(unexpected) => panic!("GetEnv returned unknown error: {unexpected}"),
+            Ok(None) => {
+                let env = self.attach(options).unwrap_or_else(|ret| match ret {
+                    JNI_EVERSION => panic!("AttachCurrentThread returned JNI_EVERSION"),
+                    ret => panic!("AttachCurrentThread returned unknown error: {ret}"),
+                });
                 if !get_thread_exit_flag() {
                     set_thread_attach_flag(self.0);
                 }
-                true
+                (env, true)
             }
-            JNI_EVERSION => panic!("GetEnv returned JNI_EVERSION"),
-            unexpected => panic!("GetEnv returned unknown error: {unexpected}"),
         };
 
-        let result = callback(unsafe { Env::from_raw(env as _) });
+        let result = callback(unsafe { Env::from_raw(env) });
 
         if just_attached && get_thread_exit_flag() {
             // this is needed in case of `with_env` is used on dropping some thread-local instance.
@@ -69,11 +82,177 @@ impl VM {
 
         result
     }
+
+    /// Attaches the current thread to the VM with `options` for the lifetime of the returned guard, e.g. for
+    /// the duration of a worker loop. The thread is detached when the guard is dropped, unless it was attached
+    /// already.
+    ///
+    /// Returns the JNI error code on failure, e.g. `JNI_EVERSION` if the VM doesn't support the
+    /// [version](AttachOptions::version) of the options.
+    pub fn attach_current_thread(&self, options: &AttachOptions) -> Result<AttachGuard, jint> {
+        let (env, detach) = match self.get_env(options.version)? {
+            Some(env) => (env, false),
+            None => (self.attach(options)?, true),
+        };
+        Ok(AttachGuard { vm: *self, env, detach })
+    }
+
+    /// Returns the `JNIEnv` of the current thread, or `None` if it is not attached.
+    fn get_env(&self, version: jint) -> Result<Option<*mut JNIEnv>, jint> {
+        let mut env = null_mut();
+        match unsafe { ((**self.0).v1_2.GetEnv)(self.0, &mut env, version) } {
+            JNI_OK => Ok(Some(env as _)),
+            JNI_EDETACHED => Ok(None),
+            err => Err(err),
+        }
+    }
+
+    /// Attaches the current thread with JNI `AttachCurrentThread` or `AttachCurrentThreadAsDaemon`.
+    fn attach(&self, options: &AttachOptions) -> Result<*mut JNIEnv, jint> {
+        let mut args = JavaVMAttachArgs {
+            version: options.version,
+            name: options.name.as_ref().map_or(null_mut(), |name| name.as_ptr() as *mut _),
+            group: options.group,
+        };
+        let args = &mut args as *mut JavaVMAttachArgs as *mut c_void;
+        let mut env = null_mut();
+        let ret = match options.daemon {
+            true => unsafe { ((**self.0).v1_4.AttachCurrentThreadAsDaemon)(self.0, &mut env, args) },
+            false => unsafe { ((**self.0).v1_2.AttachCurrentThread)(self.0, &mut env, args) },
+        };
+        if ret != JNI_OK {
+            return Err(ret);
+        }
+
+        // Not all VMs check the version of the attach args.
+        let env = env as *mut JNIEnv;
+        if unsafe { Env::from_raw(env) }.version() < options.version {
+            unsafe { ((**self.0).v1_2.DetachCurrentThread)(self.0) };
+            return Err(JNI_EVERSION);
+        }
+        Ok(env)
+    }
+
+    /// Unloads the VM with JNI `DestroyJavaVM`, blocking until all other non-daemon Java threads have exited.
+    ///
+    /// Returns the JNI error code on failure.
+    ///
+    /// # Safety
+    ///
+    /// - No copies of this `VM` nor any [Global](crate::Global) or [Local](crate::Local) reference created under it
+    ///   may be used (or dropped) afterwards.
+    /// - Other native threads that have been attached by [VM::with_env] or [VM::attach_current_thread] must have
+    ///   exited.
+    pub unsafe fn destroy(self) -> Result<(), jint> {
+        // `DestroyJavaVM` takes care of the current thread; don't detach it again at thread exit.
+        if let Ok(Some(flag)) = THREAD_ATTACH_FLAG.try_with(|flag| flag.take()) {
+            std::mem::forget(flag);
+        }
+        match unsafe { ((**self.0).v1_2.DestroyJavaVM)(self.0) } {
+            JNI_OK => Ok(()),
+            err => Err(err),
+        }
+    }
 }
 
 unsafe impl Send for VM {}
 unsafe impl Sync for VM {}
 
+/// How [VM::attach_current_thread] and [VM::with_env_options] attach a thread to the VM.
+///
+/// ```rust,no_run
+/// # use java_spaghetti::{AttachOptions, VM};
+/// # fn example(vm: VM) {
+/// let guard = vm.attach_current_thread(&AttachOptions::new().name("worker").daemon(true)).unwrap();
+/// let env = guard.env();
+/// // ...
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct AttachOptions<'a> {
+    version: jint,
+    daemon: bool,
+    name: Option<CString>,
+    group: jobject,
+    _group: PhantomData<&'a ()>,
+}
+
+impl<'a> AttachOptions<'a> {
+    /// Requires JNI 1.2, and attaches a non-daemon thread without a name to the `main` thread group.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the JNI version required from the VM, for `GetEnv` and `AttachCurrentThread`. Defaults to
+    /// `JNI_VERSION_1_2`. Functions added in later versions check [Env::version] themselves.
+    pub fn version(mut self, version: jint) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Attaches the thread as a daemon with JNI `AttachCurrentThreadAsDaemon`, so that it doesn't keep the VM
+    /// from shutting down.
+    pub fn daemon(mut self, daemon: bool) -> Self {
+        self.daemon = daemon;
+        self
+    }
+
+    /// Sets the name of the `java.lang.Thread`, instead of the generated `Thread-N`.
+    pub fn name(mut self, name: &str) -> Self {
+        // Modified UTF-8 has no NUL bytes.
+        self.name = Some(CString::new(to_modified_utf8(name)).unwrap());
+        self
+    }
+
+    /// Adds the thread to a `java.lang.ThreadGroup`.
+    pub fn group(mut self, group: &'a Global<impl ReferenceType>) -> Self {
+        self.group = group.as_raw();
+        self
+    }
+}
+
+impl Default for AttachOptions<'_> {
+    fn default() -> Self {
+        Self {
+            version: JNI_VERSION_1_2,
+            daemon: false,
+            name: None,
+            group: null_mut(),
+            _group: PhantomData,
+        }
+    }
+}
+
+/// Keeps the current thread attached to the VM, returned by [VM::attach_current_thread].
+///
+/// Dropping the guard detaches the thread with JNI `DetachCurrentThread` if it was attached by the guard. All
+/// local references created under [AttachGuard::env] are freed by then.
+pub struct AttachGuard {
+    vm: VM,
+    env: *mut JNIEnv,
+    detach: bool,
+}
+
+impl AttachGuard {
+    /// Returns the [Env] of the attached thread.
+    pub fn env(&self) -> Env<'_> {
+        unsafe { Env::from_raw(self.env) }
+    }
+
+    /// Returns the [VM] the thread is attached to.
+    pub fn vm(&self) -> VM {
+        self.vm
+    }
+}
+
+impl Drop for AttachGuard {
+    fn drop(&mut self) {
+        if self.detach {
+            unsafe { ((**self.vm.0).v1_2.DetachCurrentThread)(self.vm.0) };
+        }
+    }
+}
+
 thread_local! {
     static THREAD_ATTACH_FLAG: Cell<Option<AttachFlag>> = const { Cell::new(None) };
     static THREAD_EXIT_FLAG: OnceCell<()> = const { OnceCell::new() };